@@ -0,0 +1,401 @@
+use crate::enums::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/*
+An integer of any size, stored as its sign and the digits of its magnitude in base 2^32 with the
+least significant digit first. There are never leading zero digits and zero is never negative, so
+every number has exactly one representation and the derived equality is correct.
+ */
+#[derive(Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+/*
+Integers which fit into an i64 are always numbers, only bigger ones are big integers
+ */
+pub fn integer(value: BigInt) -> Value {
+    match value.to_i64() {
+        Some(number) => Value::Number(number),
+        None => Value::BigInt(value),
+    }
+}
+
+impl BigInt {
+    pub fn from_i64(number: i64) -> Self {
+        let magnitude = number.unsigned_abs();
+
+        BigInt::new(number < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+
+    /*
+    Parses the decimal digits of a non-negative integer
+     */
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.is_empty() {
+            return None;
+        }
+
+        let mut digits = vec![];
+        for character in text.chars() {
+            let digit = character.to_digit(10)?;
+            multiply_small(&mut digits, 10, digit);
+        }
+
+        Some(BigInt::new(false, digits))
+    }
+
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        BigInt {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.digits.first().is_some_and(|digit| digit % 2 == 1)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::new(false, self.digits.clone())
+    }
+
+    pub fn pow10(exponent: u32) -> BigInt {
+        let mut digits = vec![1];
+        for _ in 0..exponent {
+            multiply_small(&mut digits, 10, 0);
+        }
+
+        BigInt::new(false, digits)
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u64, |magnitude, digit| (magnitude << 32) | *digit as u64);
+
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.digits.iter().rev().fold(0.0, |magnitude, digit| {
+            magnitude * 4294967296.0 + *digit as f64
+        });
+
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    /*
+    Divides like integers do, rounding the quotient towards zero. The remainder has the sign of
+    the dividend. The divisor must not be zero.
+     */
+    pub fn div_rem(&self, divisor: &BigInt) -> (BigInt, BigInt) {
+        let mut quotient = vec![0; self.digits.len()];
+        let mut remainder = vec![];
+
+        for bit in (0..self.digits.len() * 32).rev() {
+            multiply_small(&mut remainder, 2, (self.digits[bit / 32] >> (bit % 32)) & 1);
+
+            if compare_digits(&remainder, &divisor.digits) != Ordering::Less {
+                remainder = subtract_digits(&remainder, &divisor.digits);
+                quotient[bit / 32] |= 1 << (bit % 32);
+            }
+        }
+
+        (
+            BigInt::new(self.negative != divisor.negative, quotient),
+            BigInt::new(self.negative, remainder),
+        )
+    }
+}
+
+/*
+digits = digits * factor + carry, for small factors and carries
+ */
+fn multiply_small(digits: &mut Vec<u32>, factor: u32, carry: u32) {
+    let mut carry = carry as u64;
+
+    for digit in digits.iter_mut() {
+        let product = *digit as u64 * factor as u64 + carry;
+        *digit = product as u32;
+        carry = product >> 32;
+    }
+
+    if carry > 0 {
+        digits.push(carry as u32);
+    }
+}
+
+fn compare_digits(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut digits = vec![];
+    let mut carry = 0u64;
+
+    for index in 0..left.len().max(right.len()) {
+        let sum =
+            *left.get(index).unwrap_or(&0) as u64 + *right.get(index).unwrap_or(&0) as u64 + carry;
+        digits.push(sum as u32);
+        carry = sum >> 32;
+    }
+
+    digits.push(carry as u32);
+    digits
+}
+
+/*
+left - right, where left is at least as big as right
+ */
+fn subtract_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut digits = vec![];
+    let mut borrow = 0i64;
+
+    for (index, digit) in left.iter().enumerate() {
+        let mut difference = *digit as i64 - *right.get(index).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+
+        digits.push(difference as u32);
+    }
+
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+
+    digits
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_digits(&self.digits, &other.digits));
+        }
+
+        match compare_digits(&self.digits, &other.digits) {
+            Ordering::Less => {
+                BigInt::new(other.negative, subtract_digits(&other.digits, &self.digits))
+            }
+            _ => BigInt::new(self.negative, subtract_digits(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: BigInt) -> BigInt {
+        self + -other
+    }
+}
+
+impl Mul for BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: BigInt) -> BigInt {
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+
+        for (i, left) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, right) in other.digits.iter().enumerate() {
+                let product = *left as u64 * *right as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+
+            digits[i + other.digits.len()] = carry as u32;
+        }
+
+        BigInt::new(self.negative != other.negative, digits)
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.digits)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_digits(&self.digits, &other.digits),
+            (true, true) => compare_digits(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/*
+Prints the number in decimal, by repeatedly dividing by the largest power of ten that fits into a
+digit
+ */
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = self.digits.clone();
+        let mut chunks = vec![];
+
+        while !digits.is_empty() {
+            let mut remainder = 0u64;
+
+            for digit in digits.iter_mut().rev() {
+                let current = (remainder << 32) | *digit as u64;
+                *digit = (current / 1_000_000_000) as u32;
+                remainder = current % 1_000_000_000;
+            }
+
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+
+            chunks.push(remainder);
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+
+                for chunk in rest.iter().rev() {
+                    write!(f, "{:09}", chunk)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        match text.strip_prefix('-') {
+            Some(text) => -BigInt::parse(text).unwrap(),
+            None => BigInt::parse(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn parse_and_print() {
+        for text in [
+            "0",
+            "7",
+            "-7",
+            "4294967296",
+            "1000000000",
+            "-123456789012345678901234567890",
+        ] {
+            assert_eq!(big(text).to_string(), text);
+        }
+
+        assert_eq!(BigInt::parse("12a"), None);
+        assert_eq!(big("-0"), big("0"));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(BigInt::from_i64(i64::MIN).to_string(), i64::MIN.to_string());
+        assert_eq!(BigInt::from_i64(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775807").to_i64(), Some(i64::MAX));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("-9223372036854775809").to_i64(), None);
+        assert_eq!(
+            big("-18446744073709551616").to_f64(),
+            -18446744073709551616.0
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+
+        assert_eq!(
+            a.clone() + b.clone(),
+            big("-864197532086419753208641975320")
+        );
+        assert_eq!(
+            a.clone() - b.clone(),
+            big("1111111110111111111011111111100")
+        );
+        assert_eq!(
+            a.clone() * b.clone(),
+            big("-121932631137021795226185032733622923332237463801111263526900")
+        );
+        assert_eq!(a.clone() - a.clone(), big("0"));
+    }
+
+    #[test]
+    fn division() {
+        let (quotient, remainder) = big("-1000000000000000000000007").div_rem(&big("1000"));
+        assert_eq!(quotient, big("-1000000000000000000000"));
+        assert_eq!(remainder, big("-7"));
+
+        let (quotient, remainder) = big("7").div_rem(&big("-123456789012345678901"));
+        assert_eq!(quotient, big("0"));
+        assert_eq!(remainder, big("7"));
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(big("-5") < big("3"));
+        assert!(big("-50000000000000000000") < big("-5"));
+        assert!(big("50000000000000000000") > big("5"));
+    }
+}
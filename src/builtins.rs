@@ -0,0 +1,551 @@
+use crate::bigint::{self, BigInt};
+use crate::decimal::{Decimal, Rounding};
+use crate::enums::Value;
+use crate::interpreter::values_equal;
+
+/*
+Calls a function value given to a builtin, like the function passed to map or filter
+ */
+pub type Callback<'a> = &'a mut dyn FnMut(&Value, Vec<Value>) -> Value;
+
+#[derive(Clone, Copy)]
+pub enum Builtin {
+    Plain(fn(Vec<Value>) -> Value),
+    HigherOrder(fn(Vec<Value>, Callback) -> Value),
+}
+
+/*
+A free call only reaches a builtin when no user defined function with the same name is in scope,
+so scripts can override them. Method calls go through get_method instead, where the builtin
+methods of a type win over user functions.
+ */
+pub fn get_builtin(name: &str) -> Option<Builtin> {
+    let builtin = match name {
+        "keys" => Builtin::Plain(keys),
+        "values" => Builtin::Plain(values),
+        "has" => Builtin::Plain(has),
+        "remove" => Builtin::Plain(remove),
+        "len" => Builtin::Plain(len),
+        "list" => Builtin::Plain(list),
+        "contains" => Builtin::Plain(contains),
+        "upper" => Builtin::Plain(upper),
+        "lower" => Builtin::Plain(lower),
+        "trim" => Builtin::Plain(trim),
+        "split" => Builtin::Plain(split),
+        "join" => Builtin::Plain(join),
+        "sum" => Builtin::Plain(sum),
+        "float" => Builtin::Plain(float),
+        "int" => Builtin::Plain(int),
+        "is_nan" => Builtin::Plain(is_nan),
+        "decimal" => Builtin::Plain(decimal),
+        "round" => Builtin::Plain(round),
+        "divide" => Builtin::Plain(divide),
+        "wrapping_add" => Builtin::Plain(wrapping_add),
+        "wrapping_sub" => Builtin::Plain(wrapping_sub),
+        "wrapping_mul" => Builtin::Plain(wrapping_mul),
+        "saturating_add" => Builtin::Plain(saturating_add),
+        "saturating_sub" => Builtin::Plain(saturating_sub),
+        "saturating_mul" => Builtin::Plain(saturating_mul),
+        "map" => Builtin::HigherOrder(map),
+        "filter" => Builtin::HigherOrder(filter),
+        "reduce" => Builtin::HigherOrder(reduce),
+        _ => return None,
+    };
+
+    Some(builtin)
+}
+
+/*
+The builtins which can be called as a method on a value of a built-in type, like list.len() or
+text.upper(). The value is passed as the first argument.
+ */
+pub fn get_method(receiver: &Value, name: &str) -> Option<Builtin> {
+    let is_method = match receiver {
+        Value::List(_) | Value::Range(_) => matches!(
+            name,
+            "len" | "list" | "contains" | "join" | "sum" | "map" | "filter" | "reduce"
+        ),
+        Value::String(_) => matches!(
+            name,
+            "len"
+                | "list"
+                | "contains"
+                | "upper"
+                | "lower"
+                | "trim"
+                | "split"
+                | "int"
+                | "float"
+                | "decimal"
+        ),
+        Value::Number(_) => matches!(
+            name,
+            "float"
+                | "int"
+                | "is_nan"
+                | "decimal"
+                | "divide"
+                | "wrapping_add"
+                | "wrapping_sub"
+                | "wrapping_mul"
+                | "saturating_add"
+                | "saturating_sub"
+                | "saturating_mul"
+        ),
+        Value::Float(_) | Value::BigInt(_) => {
+            matches!(name, "float" | "int" | "is_nan" | "decimal" | "divide")
+        }
+        Value::Decimal(_) => matches!(
+            name,
+            "float" | "int" | "is_nan" | "decimal" | "round" | "divide"
+        ),
+        Value::Map(_) => matches!(
+            name,
+            "len" | "list" | "contains" | "keys" | "values" | "has" | "remove"
+        ),
+        _ => false,
+    };
+
+    if is_method { get_builtin(name) } else { None }
+}
+
+/*
+The items of a list or the numbers of a range, for builtins which work on both
+ */
+fn items(value: &Value) -> Option<Vec<Value>> {
+    match value {
+        Value::List(list) => Some(list.clone()),
+        Value::Range(range) => Some(range.iter().map(Value::Number).collect()),
+        _ => None,
+    }
+}
+
+fn keys(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Map(map)] => Value::List(map.keys()),
+        _ => panic!("keys expects a single map argument"),
+    }
+}
+
+fn values(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Map(map)] => Value::List(map.values()),
+        _ => panic!("values expects a single map argument"),
+    }
+}
+
+fn has(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Map(map), key] => Value::Boolean(map.has(key)),
+        _ => panic!("has expects a map and a key"),
+    }
+}
+
+/*
+Values are never shared, so instead of mutating its argument remove returns a copy of the map
+without the given key.
+ */
+fn remove(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Map(map), key] => {
+            let mut map = map.clone();
+            map.remove(key);
+            Value::Map(map)
+        }
+        _ => panic!("remove expects a map and a key"),
+    }
+}
+
+fn len(arguments: Vec<Value>) -> Value {
+    let length = match arguments.as_slice() {
+        [Value::List(list)] => list.len(),
+        [Value::String(string)] => string.chars().count(),
+        [Value::Map(map)] => map.len(),
+        [Value::Range(range)] => range.len(),
+        _ => panic!("len expects a single list, string, map or range argument"),
+    };
+
+    // Ranges can be longer than the biggest i64
+    match i64::try_from(length) {
+        Ok(length) => Value::Number(length),
+        Err(_) => Value::BigInt(BigInt::parse(&length.to_string()).unwrap()),
+    }
+}
+
+fn list(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::List(list)] => Value::List(list.clone()),
+        [Value::Range(range)] => Value::List(range.iter().map(Value::Number).collect()),
+        [Value::Map(map)] => Value::List(
+            map.entries()
+                .iter()
+                .map(|(key, value)| Value::List(vec![key.clone(), value.clone()]))
+                .collect(),
+        ),
+        [Value::String(string)] => Value::List(
+            string
+                .chars()
+                .map(|character| Value::String(character.to_string()))
+                .collect(),
+        ),
+        _ => panic!("list expects a single list, range, map or string argument"),
+    }
+}
+
+fn contains(arguments: Vec<Value>) -> Value {
+    let contained = match arguments.as_slice() {
+        [Value::Range(range), Value::Number(number)] => range.contains(*number),
+        [Value::Range(_), _] => false,
+        [Value::List(list), item] => list.iter().any(|value| values_equal(value, item)),
+        [Value::String(string), Value::String(part)] => string.contains(part.as_str()),
+        [Value::Map(map), key] => map.has(key),
+        _ => panic!("contains expects a range, list, string or map and a value"),
+    };
+
+    Value::Boolean(contained)
+}
+
+fn upper(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::String(string)] => Value::String(string.to_uppercase()),
+        _ => panic!("upper expects a single string argument"),
+    }
+}
+
+fn lower(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::String(string)] => Value::String(string.to_lowercase()),
+        _ => panic!("lower expects a single string argument"),
+    }
+}
+
+fn trim(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::String(string)] => Value::String(string.trim().to_string()),
+        _ => panic!("trim expects a single string argument"),
+    }
+}
+
+fn split(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::String(string), Value::String(separator)] => Value::List(
+            string
+                .split(separator.as_str())
+                .map(|part| Value::String(part.to_string()))
+                .collect(),
+        ),
+        _ => panic!("split expects a string and a separator"),
+    }
+}
+
+fn join(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::List(list), Value::String(separator)] => {
+            let parts: Vec<String> = list
+                .iter()
+                .map(|item| match item {
+                    Value::String(string) => string.clone(),
+                    item => format!("{:?}", item),
+                })
+                .collect();
+
+            Value::String(parts.join(separator))
+        }
+        _ => panic!("join expects a list and a separator"),
+    }
+}
+
+/*
+Adds up integers as integers, which become big integers if they have to. As soon as one of the
+numbers is a float the sum is a float.
+ */
+fn sum(arguments: Vec<Value>) -> Value {
+    let [numbers] = arguments.as_slice() else {
+        panic!("sum expects a single list or range argument")
+    };
+
+    let numbers = items(numbers).unwrap_or_else(|| panic!("sum expects a list or range"));
+
+    if numbers
+        .iter()
+        .any(|number| matches!(number, Value::Float(_)))
+    {
+        return Value::Float(
+            numbers
+                .iter()
+                .map(|number| match number {
+                    Value::Number(number) => *number as f64,
+                    Value::Float(float) => *float,
+                    Value::BigInt(big) => big.to_f64(),
+                    value => panic!("sum can only add up numbers, got {:?}", value),
+                })
+                .sum(),
+        );
+    }
+
+    bigint::integer(
+        numbers
+            .iter()
+            .fold(BigInt::from_i64(0), |total, number| match number {
+                Value::Number(number) => total + BigInt::from_i64(*number),
+                Value::BigInt(big) => total + big.clone(),
+                value => panic!("sum can only add up numbers, got {:?}", value),
+            }),
+    )
+}
+
+/*
+Integer arithmetic which does not fail on overflow, for scripts which want it to wrap around
+or to stop at the smallest or largest integer instead. It works on 64-bit integers, so big
+integers are only accepted as long as they fit into one.
+ */
+fn integers(name: &str, arguments: &[Value]) -> (i64, i64) {
+    let integer = |value: &Value| match value {
+        Value::Number(number) => *number,
+        Value::BigInt(big) => big
+            .to_i64()
+            .unwrap_or_else(|| panic!("{} expects 64-bit integers, {:?} is too large", name, big)),
+        _ => panic!("{} expects two integers", name),
+    };
+
+    match arguments {
+        [left, right] => (integer(left), integer(right)),
+        _ => panic!("{} expects two integers", name),
+    }
+}
+
+fn wrapping_add(arguments: Vec<Value>) -> Value {
+    let (left, right) = integers("wrapping_add", &arguments);
+    Value::Number(left.wrapping_add(right))
+}
+
+fn wrapping_sub(arguments: Vec<Value>) -> Value {
+    let (left, right) = integers("wrapping_sub", &arguments);
+    Value::Number(left.wrapping_sub(right))
+}
+
+fn wrapping_mul(arguments: Vec<Value>) -> Value {
+    let (left, right) = integers("wrapping_mul", &arguments);
+    Value::Number(left.wrapping_mul(right))
+}
+
+fn saturating_add(arguments: Vec<Value>) -> Value {
+    let (left, right) = integers("saturating_add", &arguments);
+    Value::Number(left.saturating_add(right))
+}
+
+fn saturating_sub(arguments: Vec<Value>) -> Value {
+    let (left, right) = integers("saturating_sub", &arguments);
+    Value::Number(left.saturating_sub(right))
+}
+
+fn saturating_mul(arguments: Vec<Value>) -> Value {
+    let (left, right) = integers("saturating_mul", &arguments);
+    Value::Number(left.saturating_mul(right))
+}
+
+/*
+Converts a number or a string to a float. Strings can also spell out the special values, like
+"inf", "-inf" and "NaN".
+ */
+fn float(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Number(number)] => Value::Float(*number as f64),
+        [Value::Float(float)] => Value::Float(*float),
+        [Value::BigInt(big)] => Value::Float(big.to_f64()),
+        [Value::Decimal(decimal)] => Value::Float(decimal.to_f64()),
+        [Value::String(string)] => Value::Float(
+            string
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Cannot convert \"{}\" to a float", string)),
+        ),
+        _ => panic!("float expects a single number or string argument"),
+    }
+}
+
+/*
+Converts a number or a string to an integer, floats are rounded towards zero
+ */
+fn int(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Number(number)] => Value::Number(*number),
+        [Value::BigInt(big)] => Value::BigInt(big.clone()),
+        [Value::Decimal(decimal)] => bigint::integer(decimal.trunc()),
+        [Value::Float(float)] => {
+            if !float.is_finite()
+                || float.trunc() < i64::MIN as f64
+                || float.trunc() >= i64::MAX as f64
+            {
+                panic!("Cannot convert {} to an integer", float);
+            }
+
+            Value::Number(*float as i64)
+        }
+        [Value::String(string)] => {
+            let text = string.trim();
+            let big = match text.strip_prefix('-') {
+                Some(digits) => BigInt::parse(digits).map(|big| -big),
+                None => BigInt::parse(text),
+            };
+
+            bigint::integer(
+                big.unwrap_or_else(|| panic!("Cannot convert \"{}\" to an integer", string)),
+            )
+        }
+        _ => panic!("int expects a single number or string argument"),
+    }
+}
+
+fn is_nan(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [Value::Number(_) | Value::BigInt(_) | Value::Decimal(_)] => Value::Boolean(false),
+        [Value::Float(float)] => Value::Boolean(float.is_nan()),
+        _ => panic!("is_nan expects a single number argument"),
+    }
+}
+
+/*
+Converts a number or a string to a decimal. A float becomes the decimal it is written as, so
+0.1 becomes 0.1d and not the binary fraction closest to it.
+ */
+fn decimal(arguments: Vec<Value>) -> Value {
+    let decimal = match arguments.as_slice() {
+        [Value::Number(number)] => Decimal::from_integer(BigInt::from_i64(*number)),
+        [Value::BigInt(big)] => Decimal::from_integer(big.clone()),
+        [Value::Decimal(decimal)] => decimal.clone(),
+        [Value::Float(float)] if float.is_finite() => Decimal::parse(&float.to_string()).unwrap(),
+        [Value::String(string)] => Decimal::parse(string.trim())
+            .unwrap_or_else(|| panic!("Cannot convert \"{}\" to a decimal", string)),
+        _ => panic!("decimal expects a single integer, finite float, decimal or string argument"),
+    };
+
+    Value::Decimal(decimal)
+}
+
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(number) => Some(Decimal::from_integer(BigInt::from_i64(*number))),
+        Value::BigInt(big) => Some(Decimal::from_integer(big.clone())),
+        Value::Decimal(decimal) => Some(decimal.clone()),
+        _ => None,
+    }
+}
+
+/*
+The number of decimal places and the rounding mode, which is half_even when it is left out
+ */
+fn precision(name: &str, arguments: &[Value]) -> (u32, Rounding) {
+    let places = match arguments.first() {
+        Some(Value::Number(places)) => u32::try_from(*places)
+            .unwrap_or_else(|_| panic!("{} can't round to {} decimal places", name, places)),
+        _ => panic!("{} expects the number of decimal places", name),
+    };
+
+    let rounding = match arguments.get(1) {
+        None => Rounding::HalfEven,
+        Some(Value::String(rounding)) => Rounding::parse(rounding).unwrap_or_else(|| {
+            panic!(
+                "Unknown rounding mode '{}', expected half_even, half_up, half_down, down, up, floor or ceiling",
+                rounding
+            )
+        }),
+        Some(value) => panic!("{} expects the rounding mode as a string, got {:?}", name, value),
+    };
+
+    (places, rounding)
+}
+
+/*
+Rounds a decimal to a number of decimal places:
+    round(2.675d, 2, "half_up") is 2.68d
+ */
+fn round(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [value, rest @ ..] if (1..=2).contains(&rest.len()) && as_decimal(value).is_some() => {
+            let (places, rounding) = precision("round", rest);
+            Value::Decimal(as_decimal(value).unwrap().round(places, rounding))
+        }
+        _ => panic!(
+            "round expects a decimal, the number of decimal places and optionally a rounding mode"
+        ),
+    }
+}
+
+/*
+Divides two decimals and rounds the result to a number of decimal places:
+    divide(10d, 3, 2, "up") is 3.34d
+ */
+fn divide(arguments: Vec<Value>) -> Value {
+    match arguments.as_slice() {
+        [dividend, divisor, rest @ ..]
+            if (1..=2).contains(&rest.len())
+                && as_decimal(dividend).is_some()
+                && as_decimal(divisor).is_some() =>
+        {
+            let (places, rounding) = precision("divide", rest);
+            let divisor = as_decimal(divisor).unwrap();
+
+            if divisor.is_zero() {
+                panic!("Division by zero in divide");
+            }
+
+            Value::Decimal(
+                as_decimal(dividend)
+                    .unwrap()
+                    .divide(&divisor, places, rounding),
+            )
+        }
+        _ => panic!(
+            "divide expects two decimals or integers, the number of decimal places and optionally a rounding mode"
+        ),
+    }
+}
+
+fn map(arguments: Vec<Value>, call: Callback) -> Value {
+    match arguments.as_slice() {
+        [collection, function] if items(collection).is_some() => Value::List(
+            items(collection)
+                .unwrap()
+                .into_iter()
+                .map(|item| call(function, vec![item]))
+                .collect(),
+        ),
+        _ => panic!("map expects a list or range and a function"),
+    }
+}
+
+fn filter(arguments: Vec<Value>, call: Callback) -> Value {
+    match arguments.as_slice() {
+        [collection, function] if items(collection).is_some() => Value::List(
+            items(collection)
+                .unwrap()
+                .into_iter()
+                .filter(|item| match call(function, vec![item.clone()]) {
+                    Value::Boolean(keep) => keep,
+                    value => panic!(
+                        "The function given to filter returned {:?} instead of a boolean",
+                        value
+                    ),
+                })
+                .collect(),
+        ),
+        _ => panic!("filter expects a list or range and a function"),
+    }
+}
+
+/*
+Combines all items into one value, starting from the initial value:
+    reduce([1, 2, 3], add, 0) is add(add(add(0, 1), 2), 3)
+ */
+fn reduce(arguments: Vec<Value>, call: Callback) -> Value {
+    match arguments.as_slice() {
+        [collection, function, initial] if items(collection).is_some() => items(collection)
+            .unwrap()
+            .into_iter()
+            .fold(initial.clone(), |accumulator, item| {
+                call(function, vec![accumulator, item])
+            }),
+        _ => panic!("reduce expects a list or range, a function and an initial value"),
+    }
+}
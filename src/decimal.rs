@@ -0,0 +1,391 @@
+use crate::bigint::BigInt;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/*
+The number of decimal places a division with / keeps, unless the operands already have more
+ */
+const DIVISION_PLACES: u32 = 20;
+
+/*
+An exact decimal number, the coefficient divided by 10 to the power of the scale. 12.50 has the
+coefficient 1250 and the scale 2. The scale is kept through calculations so amounts of money keep
+their cents, but two decimals are equal whenever they have the same value, so 12.50 == 12.5.
+ */
+#[derive(Clone)]
+pub struct Decimal {
+    coefficient: BigInt,
+    scale: u32,
+}
+
+/*
+How to round away the digits which don't fit into the requested number of decimal places
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    HalfEven,
+    HalfUp,
+    HalfDown,
+    Down,
+    Up,
+    Floor,
+    Ceiling,
+}
+
+impl Rounding {
+    pub fn parse(name: &str) -> Option<Self> {
+        let rounding = match name {
+            "half_even" => Rounding::HalfEven,
+            "half_up" => Rounding::HalfUp,
+            "half_down" => Rounding::HalfDown,
+            "down" => Rounding::Down,
+            "up" => Rounding::Up,
+            "floor" => Rounding::Floor,
+            "ceiling" => Rounding::Ceiling,
+            _ => return None,
+        };
+
+        Some(rounding)
+    }
+}
+
+impl Decimal {
+    pub fn from_integer(integer: BigInt) -> Self {
+        Decimal {
+            coefficient: integer,
+            scale: 0,
+        }
+    }
+
+    /*
+    Parses decimal notation like 12.50 or -3, the scale is the number of digits after the dot
+     */
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+
+        if whole.is_empty() {
+            return None;
+        }
+
+        let coefficient = BigInt::parse(&format!("{}{}", whole, fraction))?;
+
+        Some(Decimal {
+            coefficient: if negative { -coefficient } else { coefficient },
+            scale: fraction.len() as u32,
+        })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficient.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.coefficient.is_negative()
+    }
+
+    /*
+    The coefficient when the decimal is written with the given, bigger or equal, scale
+     */
+    fn rescaled(&self, scale: u32) -> BigInt {
+        self.coefficient.clone() * BigInt::pow10(scale - self.scale)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    /*
+    The integer part, rounding towards zero
+     */
+    pub fn trunc(&self) -> BigInt {
+        self.coefficient.div_rem(&BigInt::pow10(self.scale)).0
+    }
+
+    pub fn round(&self, places: u32, rounding: Rounding) -> Decimal {
+        if places >= self.scale {
+            return Decimal {
+                coefficient: self.rescaled(places),
+                scale: places,
+            };
+        }
+
+        Decimal {
+            coefficient: round_quotient(
+                &self.coefficient,
+                &BigInt::pow10(self.scale - places),
+                rounding,
+            ),
+            scale: places,
+        }
+    }
+
+    /*
+    Divides and rounds the result to the given number of decimal places. The divisor must not be
+    zero.
+     */
+    pub fn divide(&self, divisor: &Decimal, places: u32, rounding: Rounding) -> Decimal {
+        // self / divisor * 10^places, written as a fraction of two integers
+        let numerator = self.coefficient.clone() * BigInt::pow10(divisor.scale + places);
+        let denominator = divisor.coefficient.clone() * BigInt::pow10(self.scale);
+
+        Decimal {
+            coefficient: round_quotient(&numerator, &denominator, rounding),
+            scale: places,
+        }
+    }
+
+    /*
+    Division with /, which keeps DIVISION_PLACES decimal places and drops the trailing zeros
+    that are not needed to keep the scale of the operands
+     */
+    pub fn divide_default(&self, divisor: &Decimal) -> Decimal {
+        let scale = self.scale.max(divisor.scale);
+        let mut quotient = self.divide(divisor, scale.max(DIVISION_PLACES), Rounding::HalfEven);
+
+        while quotient.scale > scale {
+            let (shorter, remainder) = quotient.coefficient.div_rem(&BigInt::pow10(1));
+            if !remainder.is_zero() {
+                break;
+            }
+
+            quotient = Decimal {
+                coefficient: shorter,
+                scale: quotient.scale - 1,
+            };
+        }
+
+        quotient
+    }
+
+    /*
+    The remainder of a division which rounds towards zero, it has the sign of the dividend.
+    The divisor must not be zero.
+     */
+    pub fn remainder(&self, divisor: &Decimal) -> Decimal {
+        let scale = self.scale.max(divisor.scale);
+
+        Decimal {
+            coefficient: self.rescaled(scale).div_rem(&divisor.rescaled(scale)).1,
+            scale,
+        }
+    }
+}
+
+/*
+numerator / denominator, rounded to an integer
+ */
+fn round_quotient(numerator: &BigInt, denominator: &BigInt, rounding: Rounding) -> BigInt {
+    let (quotient, remainder) = numerator.div_rem(denominator);
+
+    if remainder.is_zero() {
+        return quotient;
+    }
+
+    let negative = numerator.is_negative() != denominator.is_negative();
+    let half = (remainder.abs() * BigInt::from_i64(2)).cmp(&denominator.abs());
+
+    let away_from_zero = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => true,
+        Rounding::Floor => negative,
+        Rounding::Ceiling => !negative,
+        Rounding::HalfUp => half != Ordering::Less,
+        Rounding::HalfDown => half == Ordering::Greater,
+        Rounding::HalfEven => {
+            half == Ordering::Greater || (half == Ordering::Equal && quotient.is_odd())
+        }
+    };
+
+    match (away_from_zero, negative) {
+        (false, _) => quotient,
+        (true, false) => quotient + BigInt::from_i64(1),
+        (true, true) => quotient - BigInt::from_i64(1),
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+
+        Decimal {
+            coefficient: self.rescaled(scale) + other.rescaled(scale),
+            scale,
+        }
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: Decimal) -> Decimal {
+        self + -other
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: Decimal) -> Decimal {
+        Decimal {
+            coefficient: self.coefficient * other.coefficient,
+            scale: self.scale + other.scale,
+        }
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            coefficient: -self.coefficient,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescaled(scale).cmp(&other.rescaled(scale))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+/*
+Prints all decimal places of the scale, so 12.50 stays 12.50
+ */
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.coefficient.abs().to_string();
+        let scale = self.scale as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+
+        if fraction.is_empty() {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, fraction)
+        }
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_and_print() {
+        for text in [
+            "12.50",
+            "-0.05",
+            "3",
+            "0.000",
+            "-123456789012345678901234.5",
+        ] {
+            assert_eq!(decimal(text).to_string(), text);
+        }
+
+        assert!(Decimal::parse(".5").is_none());
+        assert!(Decimal::parse("1.2.3").is_none());
+    }
+
+    #[test]
+    fn exact_arithmetic() {
+        assert_eq!((decimal("0.1") + decimal("0.2")).to_string(), "0.3");
+        assert_eq!((decimal("12.50") - decimal("0.755")).to_string(), "11.745");
+        assert_eq!((decimal("1.5") * decimal("-0.20")).to_string(), "-0.300");
+        assert_eq!(decimal("12.50"), decimal("12.5"));
+        assert!(decimal("-0.01") < decimal("0"));
+    }
+
+    #[test]
+    fn rounding() {
+        let cases = [
+            ("2.5", Rounding::HalfEven, "2"),
+            ("3.5", Rounding::HalfEven, "4"),
+            ("2.5", Rounding::HalfUp, "3"),
+            ("2.5", Rounding::HalfDown, "2"),
+            ("-2.5", Rounding::HalfUp, "-3"),
+            ("2.1", Rounding::Up, "3"),
+            ("-2.9", Rounding::Down, "-2"),
+            ("-2.1", Rounding::Floor, "-3"),
+            ("-2.9", Rounding::Ceiling, "-2"),
+        ];
+
+        for (value, rounding, expected) in cases {
+            assert_eq!(
+                decimal(value).round(0, rounding).to_string(),
+                expected,
+                "{} rounded {:?}",
+                value,
+                rounding
+            );
+        }
+
+        assert_eq!(
+            decimal("1.005").round(2, Rounding::HalfUp).to_string(),
+            "1.01"
+        );
+        assert_eq!(decimal("1.5").round(3, Rounding::Down).to_string(), "1.500");
+    }
+
+    #[test]
+    fn division() {
+        assert_eq!(
+            decimal("10")
+                .divide(&decimal("3"), 2, Rounding::HalfUp)
+                .to_string(),
+            "3.33"
+        );
+        assert_eq!(
+            decimal("-2")
+                .divide(&decimal("0.3"), 1, Rounding::Floor)
+                .to_string(),
+            "-6.7"
+        );
+        assert_eq!(
+            decimal("10.00").divide_default(&decimal("4")).to_string(),
+            "2.50"
+        );
+        assert_eq!(
+            decimal("1").divide_default(&decimal("3")).to_string(),
+            "0.33333333333333333333"
+        );
+        assert_eq!(decimal("7.5").remainder(&decimal("-2")).to_string(), "1.5");
+    }
+}
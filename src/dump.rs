@@ -0,0 +1,1113 @@
+use crate::enums::{Argument, Expression, MatchArm, Parameter, Pattern, Token};
+use crate::parser::{self, Program, Span};
+use crate::tokenizer::{self, Location, Source};
+
+/*
+The output of the tokenizer and the parser in formats meant for tools and for debugging the
+parser. The syntax tree is first turned into generic nodes which each format then writes out.
+ */
+struct Node {
+    kind: &'static str,
+    location: Option<Location>,
+    attributes: Vec<(&'static str, Attribute)>,
+    children: Vec<(&'static str, Child)>,
+}
+
+enum Attribute {
+    Name(String),
+    Names(Vec<String>),
+    Text(String),
+    Number(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+enum Child {
+    One(Node),
+    Many(Vec<Node>),
+}
+
+/*
+Hands out the spans the parser recorded. Statements, methods and match arms are in the order they
+appear in the source code, while every other node comes after the nodes inside it. The tree has to
+be walked in both of these orders at once, so children are converted before their parent is.
+ */
+struct Converter<'a> {
+    source: &'a Source,
+    statements: Vec<Span>,
+    nodes: Vec<Span>,
+    next_statement: usize,
+    next_node: usize,
+}
+
+/*
+The tokens in the same formats as the syntax tree, each with the value it carries
+ */
+pub fn dump_tokens(code: &str, format: &str) -> String {
+    let source = tokenizer::scan(code);
+
+    let tokens = source
+        .tokens
+        .iter()
+        .zip(&source.locations)
+        .map(|(token, location)| Node {
+            location: Some(*location),
+            ..token_node(token)
+        })
+        .collect();
+
+    write(&Node::new("Tokens").children("tokens", tokens), format)
+}
+
+/*
+The syntax tree as json, s-expressions or a Graphviz graph in the dot language. Only json
+contains spans, which every node has. Statements, methods and match arms are spanned including the
+semicolon or comma ending them.
+ */
+pub fn dump_ast(code: &str, format: &str) -> String {
+    let source = tokenizer::scan(code);
+    let (program, spans) = parser::parse_spans(&source);
+
+    let mut converter = Converter {
+        source: &source,
+        statements: spans.statements,
+        nodes: spans.nodes,
+        next_statement: 0,
+        next_node: 0,
+    };
+    let node = converter.program(&program);
+
+    write(&node, format)
+}
+
+fn write(node: &Node, format: &str) -> String {
+    match format {
+        "json" => {
+            let mut output = String::new();
+            write_json(node, 0, &mut output);
+            output.push('\n');
+            output
+        }
+        "sexpr" => {
+            let mut output = String::new();
+            write_sexpr(node, 0, &mut output);
+            output.push('\n');
+            output
+        }
+        "dot" => {
+            let mut output =
+                String::from("digraph ast {\n  graph [ordering=out];\n  node [shape=box];\n");
+            write_dot(node, &mut 0, &mut output);
+            output.push_str("}\n");
+            output
+        }
+        _ => panic!("Unknown format '{}', expected json, sexpr or dot", format),
+    }
+}
+
+impl Node {
+    fn new(kind: &'static str) -> Node {
+        Node {
+            kind,
+            location: None,
+            attributes: vec![],
+            children: vec![],
+        }
+    }
+
+    fn attribute(mut self, name: &'static str, attribute: Attribute) -> Node {
+        self.attributes.push((name, attribute));
+        self
+    }
+
+    fn child(mut self, name: &'static str, node: Node) -> Node {
+        self.children.push((name, Child::One(node)));
+        self
+    }
+
+    fn optional_child(self, name: &'static str, node: Option<Node>) -> Node {
+        match node {
+            Some(node) => self.child(name, node),
+            None => self,
+        }
+    }
+
+    fn children(mut self, name: &'static str, nodes: Vec<Node>) -> Node {
+        self.children.push((name, Child::Many(nodes)));
+        self
+    }
+}
+
+fn name(name: &str) -> Attribute {
+    Attribute::Name(name.to_string())
+}
+
+impl Converter<'_> {
+    fn program(&mut self, program: &Program) -> Node {
+        let expressions = self.statements(&program.expressions);
+        self.spanned(Node::new("Program").children("expressions", expressions))
+    }
+
+    fn statements(&mut self, expressions: &[Expression]) -> Vec<Node> {
+        expressions
+            .iter()
+            .map(|expression| self.located(|converter| converter.expression(expression)))
+            .collect()
+    }
+
+    fn located(&mut self, convert: impl FnOnce(&mut Self) -> Node) -> Node {
+        let span = self.statements[self.next_statement];
+        self.next_statement += 1;
+
+        let mut node = convert(self);
+        node.location = self.location(span);
+
+        node
+    }
+
+    fn spanned(&mut self, mut node: Node) -> Node {
+        let span = self.nodes[self.next_node];
+        self.next_node += 1;
+
+        node.location = self.location(span);
+        node
+    }
+
+    // Only the program of an empty source has no tokens
+    fn location(&self, span: Span) -> Option<Location> {
+        (span.start < span.end).then(|| Location {
+            start: self.source.locations[span.start].start,
+            end: self.source.locations[span.end - 1].end,
+        })
+    }
+
+    fn expressions(&mut self, expressions: &[Expression]) -> Vec<Node> {
+        expressions
+            .iter()
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    fn boxed(&mut self, expression: &Option<Box<Expression>>) -> Option<Node> {
+        expression
+            .as_ref()
+            .map(|expression| self.expression(expression))
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Node {
+        let node = match expression {
+            Expression::Number(number) => {
+                Node::new("Number").attribute("value", Attribute::Number(*number))
+            }
+            Expression::Float(float) => {
+                Node::new("Float").attribute("value", Attribute::Float(*float))
+            }
+            Expression::BigInt(big) => {
+                Node::new("BigInt").attribute("value", Attribute::Text(big.to_string()))
+            }
+            Expression::Decimal(decimal) => {
+                Node::new("Decimal").attribute("value", Attribute::Text(decimal.to_string()))
+            }
+            Expression::String(string) => {
+                Node::new("String").attribute("value", Attribute::Text(string.clone()))
+            }
+            Expression::Variable(variable) => {
+                Node::new("Variable").attribute("name", name(variable))
+            }
+            Expression::ResolvedVariable {
+                name: variable,
+                depth,
+                slot,
+            } => Node::new("ResolvedVariable")
+                .attribute("name", name(variable))
+                .attribute("depth", Attribute::Number(*depth as i64))
+                .attribute("slot", Attribute::Number(*slot as i64)),
+            Expression::Boolean(boolean) => {
+                Node::new("Boolean").attribute("value", Attribute::Boolean(*boolean))
+            }
+            Expression::Prompt => Node::new("Prompt"),
+            Expression::Null => Node::new("Null"),
+            Expression::List(items) => {
+                let items = self.expressions(items);
+                Node::new("List").children("items", items)
+            }
+            Expression::Map(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let entry = Node::new("Entry")
+                            .child("key", self.expression(key))
+                            .child("value", self.expression(value));
+                        self.spanned(entry)
+                    })
+                    .collect();
+
+                Node::new("Map").children("entries", entries)
+            }
+            Expression::Block { expressions, .. } => {
+                let expressions = self.statements(expressions);
+                Node::new("Block").children("expressions", expressions)
+            }
+            Expression::Index { target, index } => Node::new("Index")
+                .child("target", self.expression(target))
+                .child("index", self.expression(index)),
+            Expression::IndexAssign {
+                target,
+                index,
+                value,
+            } => Node::new("IndexAssign")
+                .child("target", self.expression(target))
+                .child("index", self.expression(index))
+                .child("value", self.expression(value)),
+            Expression::Field { target, field } => Node::new("Field")
+                .attribute("field", name(field))
+                .child("target", self.expression(target)),
+            Expression::FieldAssign {
+                target,
+                field,
+                value,
+            } => Node::new("FieldAssign")
+                .attribute("field", name(field))
+                .child("target", self.expression(target))
+                .child("value", self.expression(value)),
+            Expression::Declare {
+                pattern,
+                value,
+                constant,
+            } => Node::new("Declare")
+                .attribute("constant", Attribute::Boolean(*constant))
+                .child("pattern", self.pattern(pattern))
+                .child("value", self.expression(value)),
+            Expression::ResolvedDeclare {
+                pattern,
+                value,
+                constant,
+                slots,
+            } => Node::new("ResolvedDeclare")
+                .attribute("constant", Attribute::Boolean(*constant))
+                .attribute(
+                    "slots",
+                    Attribute::Names(slots.iter().map(|slot| slot.to_string()).collect()),
+                )
+                .child("pattern", self.pattern(pattern))
+                .child("value", self.expression(value)),
+            Expression::Assign {
+                name: variable,
+                value,
+            } => Node::new("Assign")
+                .attribute("name", name(variable))
+                .child("value", self.expression(value)),
+            Expression::ResolvedAssign {
+                name: variable,
+                depth,
+                slot,
+                value,
+            } => Node::new("ResolvedAssign")
+                .attribute("name", name(variable))
+                .attribute("depth", Attribute::Number(*depth as i64))
+                .attribute("slot", Attribute::Number(*slot as i64))
+                .child("value", self.expression(value)),
+            Expression::CompoundAssign {
+                target,
+                operation,
+                value,
+            } => Node::new("CompoundAssign")
+                .attribute("operation", name(&format!("{:?}", operation)))
+                .child("target", self.expression(target))
+                .child("value", self.expression(value)),
+            Expression::Destructure { pattern, value } => Node::new("Destructure")
+                .child("pattern", self.pattern(pattern))
+                .child("value", self.expression(value)),
+            Expression::Yell { expression } => {
+                Node::new("Yell").child("expression", self.expression(expression))
+            }
+            Expression::Binary {
+                left,
+                operation,
+                right,
+            } => Node::new("Binary")
+                .attribute("operation", name(&format!("{:?}", operation)))
+                .child("left", self.expression(left))
+                .child("right", self.expression(right)),
+            Expression::Unary {
+                operation,
+                expression,
+            } => Node::new("Unary")
+                .attribute("operation", name(&format!("{:?}", operation)))
+                .child("expression", self.expression(expression)),
+            Expression::Not { expression } => {
+                Node::new("Not").child("expression", self.expression(expression))
+            }
+            Expression::If {
+                condition,
+                success_expression,
+                failure_expression,
+            } => Node::new("If")
+                .child("condition", self.expression(condition))
+                .child("success_expression", self.expression(success_expression))
+                .optional_child("failure_expression", self.boxed(failure_expression)),
+            Expression::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => Node::new("Range")
+                .attribute("inclusive", Attribute::Boolean(*inclusive))
+                .child("start", self.expression(start))
+                .child("end", self.expression(end))
+                .optional_child("step", self.boxed(step)),
+            Expression::Comparison {
+                left,
+                comparator,
+                right,
+            } => Node::new("Comparison")
+                .attribute("comparator", name(&format!("{:?}", comparator)))
+                .child("left", self.expression(left))
+                .child("right", self.expression(right)),
+            Expression::Logical {
+                left,
+                operator,
+                right,
+            } => Node::new("Logical")
+                .attribute("operator", name(&format!("{:?}", operator)))
+                .child("left", self.expression(left))
+                .child("right", self.expression(right)),
+            Expression::While {
+                condition,
+                expression,
+            } => Node::new("While")
+                .child("condition", self.expression(condition))
+                .child("expression", self.expression(expression)),
+            Expression::For {
+                pattern,
+                list,
+                expression,
+            } => Node::new("For")
+                .child("pattern", self.pattern(pattern))
+                .child("list", self.expression(list))
+                .child("expression", self.expression(expression)),
+            Expression::Function {
+                identifier,
+                parameters,
+                expression,
+            } => {
+                let parameters = parameters
+                    .iter()
+                    .map(|parameter| self.parameter(parameter))
+                    .collect();
+
+                Node::new("Function")
+                    .attribute("identifier", name(identifier))
+                    .children("parameters", parameters)
+                    .child("expression", self.expression(expression))
+            }
+            Expression::FunctionCall {
+                identifier,
+                parameters,
+            } => Node::new("FunctionCall")
+                .attribute("identifier", name(identifier))
+                .children("parameters", self.arguments(parameters)),
+            Expression::ResolvedFunctionCall {
+                identifier,
+                depth,
+                slot,
+                parameters,
+            } => Node::new("ResolvedFunctionCall")
+                .attribute("identifier", name(identifier))
+                .attribute("depth", Attribute::Number(*depth as i64))
+                .attribute("slot", Attribute::Number(*slot as i64))
+                .children("parameters", self.arguments(parameters)),
+            Expression::Struct { identifier, fields } => Node::new("Struct")
+                .attribute("identifier", name(identifier))
+                .attribute("fields", Attribute::Names(fields.clone())),
+            Expression::Impl {
+                identifier,
+                methods,
+            } => Node::new("Impl")
+                .attribute("identifier", name(identifier))
+                .children("methods", self.statements(methods)),
+            Expression::StructInstance { identifier, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        let field = Node::new("FieldValue")
+                            .attribute("field", name(field))
+                            .child("value", self.expression(value));
+                        self.spanned(field)
+                    })
+                    .collect();
+
+                Node::new("StructInstance")
+                    .attribute("identifier", name(identifier))
+                    .children("fields", fields)
+            }
+            Expression::Pipeline {
+                value,
+                identifier,
+                parameters,
+            } => Node::new("Pipeline")
+                .attribute("identifier", name(identifier))
+                .child("value", self.expression(value))
+                .children("parameters", self.arguments(parameters)),
+            Expression::ResolvedPipeline {
+                value,
+                identifier,
+                depth,
+                slot,
+                parameters,
+            } => Node::new("ResolvedPipeline")
+                .attribute("identifier", name(identifier))
+                .attribute("depth", Attribute::Number(*depth as i64))
+                .attribute("slot", Attribute::Number(*slot as i64))
+                .child("value", self.expression(value))
+                .children("parameters", self.arguments(parameters)),
+            Expression::MethodCall {
+                target,
+                method,
+                parameters,
+            } => Node::new("MethodCall")
+                .attribute("method", name(method))
+                .child("target", self.expression(target))
+                .children("parameters", self.arguments(parameters)),
+            Expression::Enum {
+                identifier,
+                variants,
+            } => {
+                let variants = variants
+                    .iter()
+                    .map(|(variant, payload)| {
+                        let variant = Node::new("EnumVariant")
+                            .attribute("name", name(variant))
+                            .attribute("payload", Attribute::Names(payload.clone()));
+                        self.spanned(variant)
+                    })
+                    .collect();
+
+                Node::new("Enum")
+                    .attribute("identifier", name(identifier))
+                    .children("variants", variants)
+            }
+            Expression::Variant {
+                identifier,
+                variant,
+                parameters,
+            } => Node::new("Variant")
+                .attribute("identifier", name(identifier))
+                .attribute("variant", name(variant))
+                .children("parameters", self.expressions(parameters)),
+            Expression::Match { value, arms } => {
+                let value = self.expression(value);
+                let arms = arms
+                    .iter()
+                    .map(|arm| self.located(|converter| converter.arm(arm)))
+                    .collect();
+
+                Node::new("Match")
+                    .child("value", value)
+                    .children("arms", arms)
+            }
+            Expression::Import { path, names } => {
+                let node = Node::new("Import").attribute("path", Attribute::Text(path.clone()));
+
+                match names {
+                    Some(names) => node.attribute("names", Attribute::Names(names.clone())),
+                    None => node,
+                }
+            }
+            Expression::Export { expression } => {
+                Node::new("Export").child("declaration", self.expression(expression))
+            }
+        };
+
+        self.spanned(node)
+    }
+
+    fn arguments(&mut self, arguments: &[Argument]) -> Vec<Node> {
+        arguments
+            .iter()
+            .map(|argument| {
+                let node = match argument {
+                    Argument::Positional(expression) => {
+                        Node::new("Positional").child("value", self.expression(expression))
+                    }
+                    Argument::Named(argument, expression) => Node::new("Named")
+                        .attribute("name", name(argument))
+                        .child("value", self.expression(expression)),
+                    Argument::Spread(expression) => {
+                        Node::new("Spread").child("value", self.expression(expression))
+                    }
+                };
+
+                self.spanned(node)
+            })
+            .collect()
+    }
+
+    fn parameter(&mut self, parameter: &Parameter) -> Node {
+        let pattern = self.pattern(&parameter.pattern);
+        let default = parameter
+            .default
+            .as_ref()
+            .map(|default| self.expression(default));
+
+        self.spanned(
+            Node::new("Parameter")
+                .attribute("variadic", Attribute::Boolean(parameter.variadic))
+                .child("pattern", pattern)
+                .optional_child("default", default),
+        )
+    }
+
+    fn arm(&mut self, arm: &MatchArm) -> Node {
+        let pattern = self.pattern(&arm.pattern);
+        let guard = arm.guard.as_ref().map(|guard| self.expression(guard));
+
+        let arm = Node::new("MatchArm")
+            .child("pattern", pattern)
+            .optional_child("guard", guard)
+            .child("expression", self.expression(&arm.expression));
+
+        self.spanned(arm)
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> Node {
+        let node = match pattern {
+            Pattern::Identifier(identifier) => {
+                Node::new("IdentifierPattern").attribute("name", name(identifier))
+            }
+            Pattern::Wildcard => Node::new("WildcardPattern"),
+            Pattern::Literal(literal) => {
+                Node::new("LiteralPattern").child("value", self.expression(literal))
+            }
+            Pattern::List { items, rest } => {
+                let items = items.iter().map(|item| self.pattern(item)).collect();
+                let node = Node::new("ListPattern").children("items", items);
+
+                match rest {
+                    Some(rest) => node.attribute("rest", name(rest)),
+                    None => node,
+                }
+            }
+            Pattern::Variant {
+                identifier,
+                variant,
+                payload,
+            } => {
+                let payload = payload.iter().map(|item| self.pattern(item)).collect();
+
+                Node::new("VariantPattern")
+                    .attribute("identifier", name(identifier))
+                    .attribute("variant", name(variant))
+                    .children("payload", payload)
+            }
+        };
+
+        self.spanned(node)
+    }
+}
+
+fn token_node(token: &Token) -> Node {
+    match token {
+        Token::Number(number) => Node::new("Number").attribute("value", Attribute::Number(*number)),
+        Token::Float(float) => Node::new("Float").attribute("value", Attribute::Float(*float)),
+        Token::BigInt(big) => {
+            Node::new("BigInt").attribute("value", Attribute::Text(big.to_string()))
+        }
+        Token::Decimal(decimal) => {
+            Node::new("Decimal").attribute("value", Attribute::Text(decimal.to_string()))
+        }
+        Token::String(string) => {
+            Node::new("String").attribute("value", Attribute::Text(string.clone()))
+        }
+        Token::Identifier(identifier) => {
+            Node::new("Identifier").attribute("name", name(identifier))
+        }
+        Token::Operation(operation) => {
+            Node::new("Operation").attribute("operation", name(&format!("{:?}", operation)))
+        }
+        Token::CompoundAssign(operation) => {
+            Node::new("CompoundAssign").attribute("operation", name(&format!("{:?}", operation)))
+        }
+        Token::Comparator(comparator) => {
+            Node::new("Comparator").attribute("comparator", name(&format!("{:?}", comparator)))
+        }
+        Token::ParenthesesOpen => Node::new("ParenthesesOpen"),
+        Token::ParenthesesClosed => Node::new("ParenthesesClosed"),
+        Token::BracketOpen => Node::new("BracketOpen"),
+        Token::BracketClosed => Node::new("BracketClosed"),
+        Token::Equals => Node::new("Equals"),
+        Token::Increment => Node::new("Increment"),
+        Token::Decrement => Node::new("Decrement"),
+        Token::Remember => Node::new("Remember"),
+        Token::Fix => Node::new("Fix"),
+        Token::Semicolon => Node::new("Semicolon"),
+        Token::Yell => Node::new("Yell"),
+        Token::BlockOpen => Node::new("BlockOpen"),
+        Token::BlockClosed => Node::new("BlockClosed"),
+        Token::True => Node::new("True"),
+        Token::False => Node::new("False"),
+        Token::If => Node::new("If"),
+        Token::Else => Node::new("Else"),
+        Token::Quote => Node::new("Quote"),
+        Token::While => Node::new("While"),
+        Token::Null => Node::new("Null"),
+        Token::Comma => Node::new("Comma"),
+        Token::Colon => Node::new("Colon"),
+        Token::DoubleColon => Node::new("DoubleColon"),
+        Token::FatArrow => Node::new("FatArrow"),
+        Token::Pipe => Node::new("Pipe"),
+        Token::And => Node::new("And"),
+        Token::Or => Node::new("Or"),
+        Token::Not => Node::new("Not"),
+        Token::Dot => Node::new("Dot"),
+        Token::DotDot => Node::new("DotDot"),
+        Token::DotDotEquals => Node::new("DotDotEquals"),
+        Token::Ellipsis => Node::new("Ellipsis"),
+        Token::For => Node::new("For"),
+        Token::In => Node::new("In"),
+        Token::Function => Node::new("Function"),
+        Token::Prompt => Node::new("Prompt"),
+        Token::Struct => Node::new("Struct"),
+        Token::Impl => Node::new("Impl"),
+        Token::Enum => Node::new("Enum"),
+        Token::Match => Node::new("Match"),
+        Token::Import => Node::new("Import"),
+        Token::Export => Node::new("Export"),
+    }
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            character if character.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn indent(depth: usize, output: &mut String) {
+    for _ in 0..depth {
+        output.push_str("  ");
+    }
+}
+
+fn write_json(node: &Node, depth: usize, output: &mut String) {
+    let mut fields = vec![("kind", escape_json(node.kind))];
+
+    if let Some(location) = node.location {
+        fields.push((
+            "span",
+            format!(
+                "{{ \"start\": {{ \"line\": {}, \"column\": {} }}, \"end\": {{ \"line\": {}, \"column\": {} }} }}",
+                location.start.line,
+                location.start.column,
+                location.end.line,
+                location.end.column
+            ),
+        ));
+    }
+
+    for (key, attribute) in &node.attributes {
+        let value = match attribute {
+            Attribute::Name(text) | Attribute::Text(text) => escape_json(text),
+            Attribute::Names(names) => {
+                let names: Vec<String> = names.iter().map(|name| escape_json(name)).collect();
+                format!("[{}]", names.join(", "))
+            }
+            Attribute::Number(number) => number.to_string(),
+            Attribute::Float(float) => format!("{:?}", float),
+            Attribute::Boolean(boolean) => boolean.to_string(),
+        };
+
+        fields.push((key, value));
+    }
+
+    for (key, child) in &node.children {
+        let mut value = String::new();
+
+        match child {
+            Child::One(child) => write_json(child, depth + 1, &mut value),
+            Child::Many(children) if children.is_empty() => value.push_str("[]"),
+            Child::Many(children) => {
+                value.push('[');
+
+                for (index, child) in children.iter().enumerate() {
+                    if index > 0 {
+                        value.push(',');
+                    }
+
+                    value.push('\n');
+                    indent(depth + 2, &mut value);
+                    write_json(child, depth + 2, &mut value);
+                }
+
+                value.push('\n');
+                indent(depth + 1, &mut value);
+                value.push(']');
+            }
+        }
+
+        fields.push((key, value));
+    }
+
+    output.push('{');
+
+    for (index, (key, value)) in fields.iter().enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+
+        output.push('\n');
+        indent(depth + 1, output);
+        output.push_str(&format!("\"{}\": {}", key, value));
+    }
+
+    output.push('\n');
+    indent(depth, output);
+    output.push('}');
+}
+
+/*
+A node is written as its kind followed by its attributes, with its children on the lines below:
+    (Binary Add
+      (Number 1)
+      (Number 2))
+ */
+fn write_sexpr(node: &Node, depth: usize, output: &mut String) {
+    output.push('(');
+    output.push_str(node.kind);
+
+    for (_, attribute) in &node.attributes {
+        output.push(' ');
+        output.push_str(&match attribute {
+            Attribute::Name(name) => name.clone(),
+            Attribute::Names(names) => format!("({})", names.join(" ")),
+            Attribute::Text(text) => format!("{:?}", text),
+            Attribute::Number(number) => number.to_string(),
+            Attribute::Float(float) => format!("{:?}", float),
+            Attribute::Boolean(boolean) => boolean.to_string(),
+        });
+    }
+
+    for (name, child) in &node.children {
+        output.push('\n');
+        indent(depth + 1, output);
+
+        match child {
+            Child::One(child) => write_sexpr(child, depth + 1, output),
+            Child::Many(children) => {
+                output.push('(');
+                output.push_str(name);
+
+                for child in children {
+                    output.push('\n');
+                    indent(depth + 2, output);
+                    write_sexpr(child, depth + 2, output);
+                }
+
+                output.push(')');
+            }
+        }
+    }
+
+    output.push(')');
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/*
+Writes the node and everything below it, numbering nodes in the order they are written, and
+returns the number of the node
+ */
+fn write_dot(node: &Node, count: &mut usize, output: &mut String) -> usize {
+    let id = *count;
+    *count += 1;
+
+    let mut label = escape_dot(node.kind);
+    for (key, attribute) in &node.attributes {
+        let value = match attribute {
+            Attribute::Name(name) => name.clone(),
+            Attribute::Names(names) => names.join(", "),
+            Attribute::Text(text) => format!("{:?}", text),
+            Attribute::Number(number) => number.to_string(),
+            Attribute::Float(float) => format!("{:?}", float),
+            Attribute::Boolean(boolean) => boolean.to_string(),
+        };
+
+        label.push_str(&format!("\\n{}: {}", key, escape_dot(&value)));
+    }
+
+    output.push_str(&format!("  n{} [label=\"{}\"];\n", id, label));
+
+    for (name, child) in &node.children {
+        match child {
+            Child::One(child) => {
+                let child_id = write_dot(child, count, output);
+                output.push_str(&format!(
+                    "  n{} -> n{} [label=\"{}\"];\n",
+                    id, child_id, name
+                ));
+            }
+            Child::Many(children) => {
+                for (index, child) in children.iter().enumerate() {
+                    let child_id = write_dot(child, count, output);
+                    output.push_str(&format!(
+                        "  n{} -> n{} [label=\"{}[{}]\"];\n",
+                        id, child_id, name, index
+                    ));
+                }
+            }
+        }
+    }
+
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            dump_tokens("remember x = 1;\nyell(\"a\") += 2.5", "sexpr"),
+            "(Tokens
+  (tokens
+    (Remember)
+    (Identifier x)
+    (Equals)
+    (Number 1)
+    (Semicolon)
+    (Yell)
+    (ParenthesesOpen)
+    (Quote)
+    (String \"a\")
+    (Quote)
+    (ParenthesesClosed)
+    (CompoundAssign Add)
+    (Float 2.5)))
+"
+        );
+    }
+
+    #[test]
+    fn tokens_as_json() {
+        assert_eq!(
+            dump_tokens("x\n  <", "json"),
+            r#"{
+  "kind": "Tokens",
+  "tokens": [
+    {
+      "kind": "Identifier",
+      "span": { "start": { "line": 1, "column": 1 }, "end": { "line": 1, "column": 2 } },
+      "name": "x"
+    },
+    {
+      "kind": "Comparator",
+      "span": { "start": { "line": 2, "column": 3 }, "end": { "line": 2, "column": 4 } },
+      "comparator": "LessThan"
+    }
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            dump_ast("x = -\"a\\\"", "json"),
+            r#"{
+  "kind": "Program",
+  "span": { "start": { "line": 1, "column": 1 }, "end": { "line": 1, "column": 10 } },
+  "expressions": [
+    {
+      "kind": "Assign",
+      "span": { "start": { "line": 1, "column": 1 }, "end": { "line": 1, "column": 10 } },
+      "name": "x",
+      "value": {
+        "kind": "Unary",
+        "span": { "start": { "line": 1, "column": 5 }, "end": { "line": 1, "column": 10 } },
+        "operation": "Subtract",
+        "expression": {
+          "kind": "String",
+          "span": { "start": { "line": 1, "column": 6 }, "end": { "line": 1, "column": 10 } },
+          "value": "a\\"
+        }
+      }
+    }
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn sexpr() {
+        assert_eq!(
+            dump_ast("remember [a, ...b] = f(1 + 2, c = []);", "sexpr"),
+            "(Program
+  (expressions
+    (Declare false
+      (ListPattern b
+        (items
+          (IdentifierPattern a)))
+      (FunctionCall f
+        (parameters
+          (Positional
+            (Binary Add
+              (Number 1)
+              (Number 2)))
+          (Named c
+            (List
+              (items))))))))
+"
+        );
+    }
+
+    #[test]
+    fn dot() {
+        assert_eq!(
+            dump_ast("yell(1 < 2)", "dot"),
+            "digraph ast {
+  graph [ordering=out];
+  node [shape=box];
+  n0 [label=\"Program\"];
+  n1 [label=\"Yell\"];
+  n2 [label=\"Comparison\\ncomparator: LessThan\"];
+  n3 [label=\"Number\\nvalue: 1\"];
+  n2 -> n3 [label=\"left\"];
+  n4 [label=\"Number\\nvalue: 2\"];
+  n2 -> n4 [label=\"right\"];
+  n1 -> n2 [label=\"expression\"];
+  n0 -> n1 [label=\"expressions[0]\"];
+}
+"
+        );
+    }
+
+    fn spans_of(json: &str, kind: &str) -> Vec<String> {
+        let lines: Vec<&str> = json.lines().map(str::trim).collect();
+
+        lines
+            .windows(2)
+            .filter(|pair| pair[0] == format!("\"kind\": \"{}\",", kind))
+            .map(|pair| pair[1].to_string())
+            .collect()
+    }
+
+    fn span(start: (usize, usize), end: (usize, usize)) -> String {
+        format!(
+            "\"span\": {{ \"start\": {{ \"line\": {}, \"column\": {} }}, \"end\": {{ \"line\": {}, \"column\": {} }} }},",
+            start.0, start.1, end.0, end.1
+        )
+    }
+
+    #[test]
+    fn spans_of_nested_statements() {
+        let json = dump_ast(
+            "impl P {\n  function a(self) { 1 }\n};\nmatch (x) {\n  1 => { 2; 3 },\n  _ => 4\n}",
+            "json",
+        );
+
+        assert_eq!(spans_of(&json, "Impl"), vec![span((1, 1), (3, 3))]);
+        assert_eq!(spans_of(&json, "Function"), vec![span((2, 3), (2, 25))]);
+        assert_eq!(spans_of(&json, "Match"), vec![span((4, 1), (7, 2))]);
+        assert_eq!(
+            spans_of(&json, "MatchArm"),
+            vec![span((5, 3), (5, 17)), span((6, 3), (6, 9))]
+        );
+        assert_eq!(
+            spans_of(&json, "Number"),
+            vec![
+                span((2, 22), (2, 23)),
+                span((5, 3), (5, 4)),
+                span((5, 10), (5, 12)),
+                span((5, 13), (5, 14)),
+                span((6, 8), (6, 9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_of_expressions() {
+        let json = dump_ast("yell(f(a = 1 + (2 * b)));\nx[0]++", "json");
+
+        assert_eq!(spans_of(&json, "Yell"), vec![span((1, 1), (1, 26))]);
+        assert_eq!(spans_of(&json, "FunctionCall"), vec![span((1, 6), (1, 24))]);
+        assert_eq!(spans_of(&json, "Named"), vec![span((1, 8), (1, 23))]);
+        assert_eq!(
+            spans_of(&json, "Binary"),
+            vec![span((1, 12), (1, 23)), span((1, 17), (1, 22))]
+        );
+        assert_eq!(
+            spans_of(&json, "CompoundAssign"),
+            vec![span((2, 1), (2, 7))]
+        );
+        assert_eq!(
+            spans_of(&json, "Number"),
+            vec![
+                span((1, 12), (1, 13)),
+                span((1, 17), (1, 18)),
+                span((2, 3), (2, 4)),
+                span((2, 5), (2, 7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_node_has_a_span() {
+        let code = "import { a } from \"m\";
+export fix [b, ...c] = [1, 2.5, {\"k\": null}];
+struct P { x };
+impl P { function get(self, d = 1, ...e) { self.x } };
+enum E { A, B(v) };
+p = P { x: E::B(1) };
+p.x = -a[0]..10 step 2;
+p.x.y += 1;
+c[0]--;
+for ([i, _] in xs |> f(n = 1, ...ys)) { while (!true && false || 1 < 2) { yell(i.m(1)) } };
+if (match (p) { E::B(v) if (v) => v, _ => 2 }) { 1 } else { prompt() }";
+        let json = dump_ast(code, "json");
+
+        assert_eq!(
+            json.matches("\"kind\"").count(),
+            json.matches("\"span\"").count()
+        );
+        assert_eq!(spans_of(&json, "Program"), vec![span((1, 1), (11, 71))]);
+        assert_eq!(
+            spans_of(&json, "Parameter"),
+            vec![
+                span((4, 23), (4, 27)),
+                span((4, 29), (4, 34)),
+                span((4, 36), (4, 40)),
+            ]
+        );
+        assert_eq!(
+            spans_of(&json, "EnumVariant"),
+            vec![span((5, 10), (5, 11)), span((5, 13), (5, 17))]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown format 'xml'")]
+    fn unknown_format() {
+        dump_ast("1", "xml");
+    }
+}
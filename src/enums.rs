@@ -1,20 +1,85 @@
+use crate::bigint::BigInt;
+use crate::decimal::Decimal;
+use crate::environment::Function;
+use crate::instance::{Instance, Variant};
+use crate::map::Map;
+use crate::range::Range;
+use crate::tokenizer::Position;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Number(i64),
     Float(f64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     String(String),
     Variable(String),
+    // A variable the resolver found in the scope depth scopes up, stored at the given slot
+    ResolvedVariable {
+        name: String,
+        depth: usize,
+        slot: usize,
+    },
     Boolean(bool),
     List(Vec<Expression>),
+    Map(Vec<(Expression, Expression)>),
     Prompt,
     Null,
     Block {
         expressions: Vec<Expression>,
+        // Where each statement starts, like the positions of a program
+        positions: Vec<Position>,
+    },
+    Declare {
+        pattern: Pattern,
+        value: Box<Expression>,
+        constant: bool,
+    },
+    // A declaration whose names the resolver put into the given slots of the current scope
+    ResolvedDeclare {
+        pattern: Pattern,
+        value: Box<Expression>,
+        constant: bool,
+        slots: Vec<usize>,
     },
     Assign {
         name: String,
         value: Box<Expression>,
     },
+    // An assignment to a variable the resolver found, like a ResolvedVariable
+    ResolvedAssign {
+        name: String,
+        depth: usize,
+        slot: usize,
+        value: Box<Expression>,
+    },
+    Index {
+        target: Box<Expression>,
+        index: Box<Expression>,
+    },
+    IndexAssign {
+        target: Box<Expression>,
+        index: Box<Expression>,
+        value: Box<Expression>,
+    },
+    Field {
+        target: Box<Expression>,
+        field: String,
+    },
+    FieldAssign {
+        target: Box<Expression>,
+        field: String,
+        value: Box<Expression>,
+    },
+    CompoundAssign {
+        target: Box<Expression>,
+        operation: Operation,
+        value: Box<Expression>,
+    },
+    Destructure {
+        pattern: Pattern,
+        value: Box<Expression>,
+    },
     Yell {
         expression: Box<Expression>,
     },
@@ -32,6 +97,12 @@ pub enum Expression {
         success_expression: Box<Expression>,
         failure_expression: Option<Box<Expression>>,
     },
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        step: Option<Box<Expression>>,
+        inclusive: bool,
+    },
     Comparison {
         left: Box<Expression>,
         comparator: Comparator,
@@ -42,19 +113,121 @@ pub enum Expression {
         expression: Box<Expression>,
     },
     For {
-        identifier: String,
+        pattern: Pattern,
         list: Box<Expression>,
         expression: Box<Expression>,
     },
     Function {
         identifier: String,
-        parameters: Vec<String>,
+        parameters: Vec<Parameter>,
         expression: Box<Expression>,
     },
     FunctionCall {
         identifier: String,
+        parameters: Vec<Argument>,
+    },
+    // A call of a function the resolver found, like a ResolvedVariable
+    ResolvedFunctionCall {
+        identifier: String,
+        depth: usize,
+        slot: usize,
+        parameters: Vec<Argument>,
+    },
+    Struct {
+        identifier: String,
+        fields: Vec<String>,
+    },
+    Impl {
+        identifier: String,
+        methods: Vec<Expression>,
+    },
+    StructInstance {
+        identifier: String,
+        fields: Vec<(String, Expression)>,
+    },
+    MethodCall {
+        target: Box<Expression>,
+        method: String,
+        parameters: Vec<Argument>,
+    },
+    Pipeline {
+        value: Box<Expression>,
+        identifier: String,
+        parameters: Vec<Argument>,
+    },
+    // A pipeline into a function the resolver found
+    ResolvedPipeline {
+        value: Box<Expression>,
+        identifier: String,
+        depth: usize,
+        slot: usize,
+        parameters: Vec<Argument>,
+    },
+    Enum {
+        identifier: String,
+        variants: Vec<(String, Vec<String>)>,
+    },
+    Variant {
+        identifier: String,
+        variant: String,
         parameters: Vec<Expression>,
     },
+    Match {
+        value: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    Logical {
+        left: Box<Expression>,
+        operator: Logical,
+        right: Box<Expression>,
+    },
+    Not {
+        expression: Box<Expression>,
+    },
+    Import {
+        path: String,
+        names: Option<Vec<String>>,
+    },
+    Export {
+        expression: Box<Expression>,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Parameter {
+    pub pattern: Pattern,
+    pub default: Option<Expression>,
+    pub variadic: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Argument {
+    Positional(Expression),
+    Named(String, Expression),
+    Spread(Expression),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub expression: Expression,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Identifier(String),
+    Wildcard,
+    Literal(Box<Expression>),
+    List {
+        items: Vec<Pattern>,
+        rest: Option<String>,
+    },
+    Variant {
+        identifier: String,
+        variant: String,
+        payload: Vec<Pattern>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -63,18 +236,28 @@ pub enum Operation {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
 }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Logical {
+    And,
+    Or,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Comparator {
     Equality,
     LessThan,
     GreaterThan,
+    In,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Number(i64),
     Float(f64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Operation(Operation),
     ParenthesesOpen,
     ParenthesesClosed,
@@ -82,7 +265,11 @@ pub enum Token {
     BracketClosed,
     Identifier(String),
     Equals,
+    CompoundAssign(Operation),
+    Increment,
+    Decrement,
     Remember,
+    Fix,
     Semicolon,
     Yell,
     BlockOpen,
@@ -97,18 +284,42 @@ pub enum Token {
     While,
     Null,
     Comma,
+    Colon,
+    DoubleColon,
+    FatArrow,
+    Pipe,
+    And,
+    Or,
+    Not,
+    Dot,
+    DotDot,
+    DotDotEquals,
+    Ellipsis,
     For,
     In,
     Function,
     Prompt,
+    Struct,
+    Impl,
+    Enum,
+    Match,
+    Import,
+    Export,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Number(i64),
     Float(f64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Boolean(bool),
     String(String),
     List(Vec<Value>),
+    Map(Map),
+    Struct(Instance),
+    Variant(Variant),
+    Range(Range),
+    Function(Function),
     Null,
 }
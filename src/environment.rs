@@ -1,28 +1,179 @@
-use crate::enums::Expression;
 use crate::enums::Value;
+use crate::enums::{Expression, Parameter};
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+/*
+The body and parameters are shared, so looking a function up to call it doesn't copy them
+ */
+#[derive(Clone)]
+pub struct FunctionDefinition {
+    pub expression: Rc<Expression>,
+    pub parameters: Rc<Vec<Parameter>>,
+    pub closure: Closure,
+}
+
+impl FunctionDefinition {
+    pub fn strong(self) -> FunctionDefinition {
+        FunctionDefinition {
+            closure: self.closure.strong(),
+            ..self
+        }
+    }
+}
+
+/*
+The scope a function was defined in. A declared function is stored in that very scope, so it only
+refers to it weakly, otherwise the two would keep each other alive forever. As soon as the
+function is taken out of its scope, as a value, it holds on to the scope strongly.
+ */
+#[derive(Clone)]
+pub enum Closure {
+    Strong(Rc<RefCell<Environment>>),
+    Weak(Weak<RefCell<Environment>>),
+}
+
+impl Closure {
+    pub fn env(&self) -> Rc<RefCell<Environment>> {
+        match self {
+            Closure::Strong(env) => env.clone(),
+            Closure::Weak(env) => env
+                .upgrade()
+                .expect("A function outlived the scope it was declared in"),
+        }
+    }
+
+    pub fn strong(self) -> Closure {
+        Closure::Strong(self.env())
+    }
+
+    fn as_ptr(&self) -> *const RefCell<Environment> {
+        match self {
+            Closure::Strong(env) => Rc::as_ptr(env),
+            Closure::Weak(env) => env.as_ptr(),
+        }
+    }
+}
+
+/*
+A function used as a value, for example when it is passed to another function. It is either one
+defined in the script or a builtin.
+ */
+#[derive(Clone)]
+pub enum Function {
+    Defined {
+        identifier: String,
+        definition: FunctionDefinition,
+    },
+    Builtin(String),
+}
+
+impl Function {
+    pub fn identifier(&self) -> &str {
+        match self {
+            Function::Defined { identifier, .. } => identifier,
+            Function::Builtin(identifier) => identifier,
+        }
+    }
+}
+
+/*
+Two function values are only equal when they come from the same definition in the same scope
+ */
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Function::Defined {
+                    identifier,
+                    definition,
+                },
+                Function::Defined {
+                    identifier: other_identifier,
+                    definition: other_definition,
+                },
+            ) => {
+                identifier == other_identifier
+                    && definition.closure.as_ptr() == other_definition.closure.as_ptr()
+                    && definition.expression == other_definition.expression
+            }
+            (Function::Builtin(identifier), Function::Builtin(other_identifier)) => {
+                identifier == other_identifier
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.identifier())
+    }
+}
+
+/*
+A struct is shared by its scope and all its instances, so methods added by a later impl are
+available on the instances created before
+ */
+pub struct StructDefinition {
+    pub fields: Vec<String>,
+    pub methods: RefCell<HashMap<String, FunctionDefinition>>,
+}
+
+#[derive(Clone)]
+pub struct EnumDefinition {
+    pub variants: Vec<(String, Vec<String>)>,
+}
 
 #[derive(Clone)]
 pub enum EnvironmentRecord {
     Value(Value),
-    Function {
-        expression: Box<Expression>,
-        parameters: Vec<String>,
-    },
+    Constant(Value),
+    Function(FunctionDefinition),
+    Struct(Rc<StructDefinition>),
+    Enum(EnumDefinition),
 }
 
+/*
+A scope, which stores its records in slots in the order they were declared. The resolver works
+out the same order before the program runs, so a variable can be read, assigned and declared
+through its slot alone. Everything else finds records by searching the names of a scope, which
+are few enough that an index would cost more than it saves.
+ */
 #[derive(Clone)]
 pub struct Environment {
-    pub records: HashMap<String, EnvironmentRecord>,
+    // The name in every slot
+    names: Vec<String>,
+    records: Vec<EnvironmentRecord>,
     pub parent: Option<Rc<RefCell<Environment>>>,
 }
 
 impl Environment {
+    pub fn new(parent: Option<Rc<RefCell<Environment>>>) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment {
+            names: vec![],
+            records: vec![],
+            parent,
+        }))
+    }
+
+    fn slot(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|existing| existing == name)
+    }
+
+    fn overwrite(&mut self, slot: usize, name: &str, record: EnvironmentRecord) {
+        if let EnvironmentRecord::Constant(_) = self.records[slot] {
+            panic!("Cannot assign to constant '{}'", name);
+        }
+
+        self.records[slot] = record;
+    }
+
     pub fn get(&self, name: &str) -> Option<EnvironmentRecord> {
-        if let Some(value) = self.records.get(name) {
-            Some(value.clone())
+        if let Some(slot) = self.slot(name) {
+            Some(self.records[slot].clone())
         } else if let Some(parent) = &self.parent {
             parent.borrow().get(name)
         } else {
@@ -30,24 +181,129 @@ impl Environment {
         }
     }
 
+    /*
+    The record in the given slot of the scope depth scopes up. It is missing while the program
+    has not reached its declaration yet, which happens when a function refers to a variable
+    declared after it and is called before that.
+     */
+    pub fn get_slot(&self, depth: usize, slot: usize) -> Option<EnvironmentRecord> {
+        match depth {
+            0 => self.records.get(slot).cloned(),
+            _ => self
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().get_slot(depth - 1, slot)),
+        }
+    }
+
+    pub fn assign_slot(
+        &mut self,
+        depth: usize,
+        slot: usize,
+        name: &str,
+        record: EnvironmentRecord,
+    ) {
+        match depth {
+            0 if slot < self.records.len() => self.overwrite(slot, name, record),
+            0 => panic!("Variable '{}' is used before its declaration", name),
+            _ => self.parent.as_ref().unwrap().borrow_mut().assign_slot(
+                depth - 1,
+                slot,
+                name,
+                record,
+            ),
+        }
+    }
+
+    /*
+    Declares a name in the slot the resolver gave it, which is either the slot of an earlier
+    declaration of the name or the next free one
+     */
+    pub fn declare_slot(&mut self, slot: usize, name: &str, record: EnvironmentRecord) {
+        if slot < self.records.len() {
+            self.redeclare(slot, name, record);
+        } else {
+            self.names.push(name.to_string());
+            self.records.push(record);
+        }
+    }
+
+    fn redeclare(&mut self, slot: usize, name: &str, record: EnvironmentRecord) {
+        if let EnvironmentRecord::Constant(_) = self.records[slot] {
+            panic!("Cannot redeclare constant '{}'", name);
+        }
+
+        self.records[slot] = record
+    }
+
+    /*
+    Declaring always binds in the current scope, shadowing any binding of the same name in the
+    scopes around it. Declaring a name again reuses its slot, unless it holds a constant, which
+    can't be replaced in its scope by any kind of declaration.
+     */
+    pub fn declare(&mut self, name: String, record: EnvironmentRecord) {
+        match self.slot(&name) {
+            Some(slot) => self.redeclare(slot, &name, record),
+            None => {
+                self.names.push(name);
+                self.records.push(record);
+            }
+        }
+    }
+
+    /*
+    Assigning replaces the binding in the closest scope that declared it. Constants can't be
+    replaced and names that were never declared can't be assigned to.
+     */
+    pub fn assign(&mut self, name: &str, record: EnvironmentRecord) {
+        if let Some(slot) = self.slot(name) {
+            self.overwrite(slot, name, record);
+            return;
+        }
+
+        match &self.parent {
+            Some(parent) => parent.borrow_mut().assign(name, record),
+            None => panic!("Cannot assign to undeclared variable '{}'", name),
+        }
+    }
+
     pub fn set(&mut self, name: String, record: EnvironmentRecord) {
-        if self.records.contains_key(&name) {
-            self.records.insert(name, record);
+        if let Some(slot) = self.slot(&name) {
+            self.overwrite(slot, &name, record);
             return;
         }
 
-        if let Some(parent) = &self.parent {
-            if parent.borrow().has(&name) {
-                parent.borrow_mut().set(name, record);
-                return;
-            }
+        if let Some(parent) = &self.parent
+            && parent.borrow().has(&name)
+        {
+            parent.borrow_mut().set(name, record);
+            return;
+        }
+
+        self.declare(name, record);
+    }
+
+    pub fn declares(&self, name: &str) -> bool {
+        self.slot(name).is_some()
+    }
+
+    /*
+    The scope which declares the name, either the given one or one of the scopes around it
+     */
+    pub fn scope_of(
+        env: &Rc<RefCell<Environment>>,
+        name: &str,
+    ) -> Option<Rc<RefCell<Environment>>> {
+        if env.borrow().declares(name) {
+            return Some(env.clone());
         }
 
-        self.records.insert(name, record);
+        let parent = env.borrow().parent.clone();
+        parent.and_then(|parent| Environment::scope_of(&parent, name))
     }
 
     pub fn has(&self, name: &str) -> bool {
-        if self.records.contains_key(name) {
+        if self.slot(name).is_some() {
             return true;
         }
 
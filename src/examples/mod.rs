@@ -22,5 +22,6 @@ mod tests {
         run_file("./src/examples/while_loop.nali".to_string());
         run_file("./src/examples/if.nali".to_string());
         run_file("./src/examples/function.nali".to_string());
+        run_file("./src/examples/struct.nali".to_string());
     }
 }
@@ -0,0 +1,107 @@
+use crate::enums::Value;
+use crate::environment::{Environment, StructDefinition};
+use crate::interpreter::values_equal;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+/*
+An instance of a user defined struct. The fields are kept in the order they were declared in,
+which gives us structural equality and a stable printed representation for free.
+
+Its methods are looked up in the definition it was created from, not by its name, so they are
+still found when the struct is shadowed or declared again. The instance also keeps the scope of
+the struct alive, which its methods may only refer to weakly.
+ */
+#[derive(Clone)]
+pub struct Instance {
+    pub identifier: String,
+    pub fields: Vec<(String, Value)>,
+    pub definition: Rc<StructDefinition>,
+    // Only held to keep the scope alive
+    pub _scope: Rc<RefCell<Environment>>,
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        self.identifier == other.identifier
+            && self.fields.len() == other.fields.len()
+            && self.fields.iter().zip(&other.fields).all(
+                |((name, value), (other_name, other_value))| {
+                    name == other_name && values_equal(value, other_value)
+                },
+            )
+    }
+}
+
+impl Instance {
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+    }
+
+    pub fn set(&mut self, field: &str, value: Value) {
+        match self.fields.iter_mut().find(|(name, _)| name == field) {
+            Some(entry) => entry.1 = value,
+            None => panic!(
+                "Struct {} does not have a field '{}'",
+                self.identifier, field
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug_struct = f.debug_struct(&self.identifier);
+
+        for (name, value) in &self.fields {
+            debug_struct.field(name, value);
+        }
+
+        debug_struct.finish()
+    }
+}
+
+/*
+An instance of one variant of a user defined enum together with its payload
+ */
+#[derive(Clone)]
+pub struct Variant {
+    pub identifier: String,
+    pub variant: String,
+    pub payload: Vec<Value>,
+}
+
+impl PartialEq for Variant {
+    fn eq(&self, other: &Self) -> bool {
+        self.identifier == other.identifier
+            && self.variant == other.variant
+            && self.payload.len() == other.payload.len()
+            && self
+                .payload
+                .iter()
+                .zip(&other.payload)
+                .all(|(value, other_value)| values_equal(value, other_value))
+    }
+}
+
+impl fmt::Debug for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.identifier, self.variant)?;
+
+        if self.payload.is_empty() {
+            return Ok(());
+        }
+
+        let payload: Vec<String> = self
+            .payload
+            .iter()
+            .map(|value| format!("{:?}", value))
+            .collect();
+
+        write!(f, "({})", payload.join(", "))
+    }
+}
@@ -1,615 +1,3980 @@
-use crate::enums::{Comparator, Expression};
+use crate::bigint::{self, BigInt};
+use crate::builtins::Builtin;
+use crate::decimal::Decimal;
+use crate::enums::{Argument, Comparator, Expression, Logical, Parameter, Pattern};
 use crate::enums::{Operation, Value};
-use crate::environment::{Environment, EnvironmentRecord};
+use crate::environment::{
+    Closure, EnumDefinition, Environment, EnvironmentRecord, Function, FunctionDefinition,
+    StructDefinition,
+};
+use crate::instance::{Instance, Variant};
+use crate::map::Map;
 use crate::parser::Program;
-use crate::{parser, tokenizer};
+use crate::range::Range;
+use crate::tokenizer::Position;
+use crate::{builtins, parser, printer, resolver, tokenizer};
 use io::stdin;
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::io;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::{fs, io, panic, thread};
+
+const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/*
+Every call of a nali function nests a few calls of interpret_expression, which take a lot of
+stack in debug builds. This is plenty for both debug and release builds. Expressions nested
+deeply within a call can still take more, so the interpreter also watches how much of the stack
+it uses and stops one call's worth before the end.
+ */
+const STACK_PER_CALL: usize = 256 * 1024;
+
+/*
+Settings which change how programs run
+ */
+#[derive(Clone, Copy)]
+pub struct Options {
+    // Conditions have to be booleans instead of being truthy or falsy
+    pub strict: bool,
+    // for loops write their variables into the surrounding scope, like they used to
+    pub leaky_loops: bool,
+    // How many calls of nali functions can be nested before the program is stopped
+    pub max_call_depth: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            strict: false,
+            leaky_loops: false,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+}
+
+impl Options {
+    /*
+    The stack a thread needs to reach the maximum call depth without overflowing. Whether a stack
+    that large can actually be reserved only shows when the thread is started.
+     */
+    pub fn stack_size(&self) -> usize {
+        self.max_call_depth
+            .checked_add(1)
+            .and_then(|calls| calls.checked_mul(STACK_PER_CALL))
+            .unwrap_or_else(|| panic!("Maximum call depth {} is too large", self.max_call_depth))
+    }
+}
 
+/*
+Runs the interpreter on a dedicated thread with the given stack size and waits for it. The stack
+of the main thread is too small to reach the default maximum call depth, so anything that runs
+programs which recurse deeply should go through here, with Options::stack_size. Panics are
+passed on to the calling thread.
+ */
+pub fn run_on_stack<T: Send>(stack_size: usize, run: impl FnOnce() -> T + Send) -> T {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .name("interpreter".to_string())
+            .stack_size(stack_size)
+            .spawn_scoped(scope, run)
+            .unwrap_or_else(|error| {
+                panic!(
+                    "Cannot start the interpreter with a stack of {} bytes: {}",
+                    stack_size, error
+                )
+            })
+            .join()
+            .unwrap_or_else(|error| panic::resume_unwind(error))
+    })
+}
+
+/*
+Runs the code with the default options, which is what most tests want
+ */
+#[cfg(test)]
 pub fn execute_interpreter(input: &str) -> Value {
-    let tokens = tokenizer::tokenize(input);
+    execute_with_options(input, Options::default())
+}
 
-    let ast = parser::parse(&tokens);
+pub fn execute_with_options(input: &str, options: Options) -> Value {
+    let mut ast = parser::parse_source(&tokenizer::scan(input));
 
-    let env = Rc::new(RefCell::new(Environment {
-        records: HashMap::new(),
-        parent: None,
-    }));
+    let mut interpreter = Interpreter::new(options);
+    let env = Environment::new(None);
 
-    interpret(&ast, &env)
+    interpreter.run_program(&mut ast, &env)
 }
 
-fn interpret(program: &Program, env: &Rc<RefCell<Environment>>) -> Value {
-    let mut result: Value = Value::Number(0);
+/*
+Runs a .nali file, whose imports are resolved relative to the directory the file is in
+ */
+pub fn execute_file(path: &str, options: Options) -> Value {
+    let path =
+        fs::canonicalize(path).unwrap_or_else(|error| panic!("Cannot run {}: {}", path, error));
 
-    for expression in &program.expressions {
-        result = interpret_expression(&expression, env)
-    }
+    let mut interpreter = Interpreter::new(options);
+    interpreter.run_module(&path).0
+}
 
-    result
+type Exports = HashMap<String, EnvironmentRecord>;
+
+/*
+Every module is evaluated once, later imports of the same file get the exports recorded the first
+time. The scope of a module is kept as well, since the functions it exports only refer to it
+weakly. The modules currently being evaluated are kept on a stack, innermost last, to resolve
+relative imports and to detect circular ones.
+ */
+struct Interpreter {
+    options: Options,
+    modules: HashMap<PathBuf, (Rc<RefCell<Environment>>, Exports)>,
+    loading: Vec<PathBuf>,
+    // The functions that are currently running, innermost last
+    calls: Vec<String>,
+    // Where the innermost statement that is running starts
+    position: Option<Position>,
+    // Where the stack was when the interpreter was created, to measure how much of it is used
+    stack_start: usize,
 }
 
-fn interpret_expression(expression: &Expression, env: &Rc<RefCell<Environment>>) -> Value {
-    match expression {
-        Expression::Number(n) => Value::Number(*n),
-        Expression::Float(f) => Value::Float(*f),
-        Expression::Boolean(boolean) => Value::Boolean(*boolean),
-        Expression::String(string) => Value::String(string.clone()),
-        Expression::List(items) => {
-            let mut list: Vec<Value> = vec![];
-            for item in items {
-                let item_evaluated = interpret_expression(item, env);
-                list.push(item_evaluated);
-            }
+impl Interpreter {
+    fn new(options: Options) -> Self {
+        Interpreter {
+            options,
+            modules: HashMap::new(),
+            loading: vec![],
+            calls: vec![],
+            position: None,
+            stack_start: stack_address(),
+        }
+    }
 
-            Value::List(list)
+    /*
+    Stops the program before it overflows the stack of the thread it runs on, which is assumed
+    to be as large as Options::stack_size asks for
+     */
+    fn check_stack(&self) {
+        let used = self.stack_start.abs_diff(stack_address());
+        let available = self.options.max_call_depth.saturating_mul(STACK_PER_CALL);
+
+        if used > available {
+            let chain = match self.calls.is_empty() {
+                true => String::new(),
+                false => format!(": {}", call_chain(&self.calls)),
+            };
+
+            panic!(
+                "Maximum stack size exceeded after {} nested calls, expressions are nested too deeply{}",
+                self.calls.len(),
+                chain
+            );
         }
-        Expression::Null => Value::Null,
-        Expression::Binary {
-            left,
-            operation,
-            right,
-        } => {
-            let left_evaluated = interpret_expression(left, env);
-            let right_evaluated = interpret_expression(right, env);
-
-            if let Value::Number(left_evaluated_number) = left_evaluated {
-                if let Value::Number(right_evaluated_number) = right_evaluated {
-                    match operation {
-                        Operation::Add => {
-                            Value::Number(left_evaluated_number + right_evaluated_number)
-                        }
-                        Operation::Subtract => {
-                            Value::Number(left_evaluated_number - right_evaluated_number)
-                        }
-                        Operation::Multiply => {
-                            Value::Number(left_evaluated_number * right_evaluated_number)
-                        }
-                        Operation::Divide => {
-                            Value::Number(left_evaluated_number / right_evaluated_number)
-                        }
-                    }
-                } else {
-                    panic!("Right side of numeric operation is not numeric")
-                }
-            } else {
-                panic!("The transpiler does not currently support non numeric binary expressions")
+    }
+
+    /*
+    Points at the expression which failed by printing it, together with where the statement it is
+    part of starts
+     */
+    fn runtime_error(&self, error: &str, expression: &Expression) -> ! {
+        let expression = printer::print_expression(expression);
+
+        match self.position {
+            Some(position) => panic!(
+                "{} in '{}' at {}:{}",
+                error, expression, position.line, position.column
+            ),
+            None => panic!("{} in '{}'", error, expression),
+        }
+    }
+
+    /*
+    Runs a statement of a program or a block and remembers where it starts while it runs, so a
+    runtime error can point at it
+     */
+    fn interpret_statement(
+        &mut self,
+        expression: &Expression,
+        position: Option<&Position>,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Value {
+        let outer = self.position;
+        if let Some(position) = position {
+            self.position = Some(*position);
+        }
+
+        let value = self.interpret_expression(expression, env);
+        self.position = outer;
+
+        value
+    }
+
+    /*
+    Runs one iteration of a for loop. Every iteration binds the loop variables in a fresh scope,
+    so they don't outlive the loop and closures created in the body keep their own iteration.
+     */
+    fn iterate(
+        &mut self,
+        pattern: &Pattern,
+        item: Value,
+        expression: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) {
+        if self.options.leaky_loops {
+            for (name, value) in destructure(pattern, item) {
+                env.borrow_mut().set(name, EnvironmentRecord::Value(value));
             }
+            self.interpret_expression(expression, env);
+            return;
         }
-        Expression::Unary {
-            operation,
-            expression,
-        } => {
-            let expression_evaluated = interpret_expression(expression, env);
 
-            if let Value::Number(expression_evaluated_number) = expression_evaluated {
-                match operation {
-                    Operation::Add => Value::Number(expression_evaluated_number),
-                    Operation::Subtract => Value::Number(expression_evaluated_number * -1),
-                    _ => panic!("You can only use add an subtract for unary operators"),
+        let iteration_env = Environment::new(Some(env.clone()));
+
+        for (name, value) in destructure(pattern, item) {
+            iteration_env
+                .borrow_mut()
+                .declare(name, EnvironmentRecord::Value(value));
+        }
+        self.interpret_expression(expression, &iteration_env);
+    }
+
+    /*
+    Evaluates the condition of an if, a while, a match guard or an operand of a logical operator.
+    In strict mode it has to be a boolean, otherwise any value is truthy or falsy.
+     */
+    fn condition(
+        &mut self,
+        expression: &Expression,
+        env: &Rc<RefCell<Environment>>,
+        context: &str,
+    ) -> bool {
+        match self.interpret_expression(expression, env) {
+            Value::Boolean(boolean) => boolean,
+            value if self.options.strict => panic!(
+                "Type error: {} expects a boolean in strict mode, got {:?} from '{}'",
+                context,
+                value,
+                printer::print_expression(expression)
+            ),
+            value => is_truthy_value(value),
+        }
+    }
+
+    /*
+    The imports of a program are loaded before any of its statements run, so what it imports is
+    known when resolving it.
+     */
+    fn run_program(&mut self, program: &mut Program, env: &Rc<RefCell<Environment>>) -> Value {
+        let mut imported = vec![];
+
+        for expression in &program.expressions {
+            if let Expression::Import { path, names } = expression {
+                let exports = self.import(path);
+                let names = match names {
+                    Some(names) => names.clone(),
+                    None => exports.keys().cloned().collect(),
+                };
+
+                for name in names {
+                    let record = exports
+                        .get(&name)
+                        .unwrap_or_else(|| panic!("Module {} does not export '{}'", path, name));
+                    env.borrow_mut().declare(name.clone(), record.clone());
+                    imported.push(name);
                 }
-            } else {
-                panic!("Right side of numeric operation is not numeric")
             }
         }
-        Expression::Assign { name, value } => {
-            let value_evaluated = interpret_expression(value, env);
-            env.borrow_mut().set(
-                name.clone(),
-                EnvironmentRecord::Value(value_evaluated.clone()),
-            );
-            value_evaluated
+
+        resolver::resolve(program, &imported, self.options.leaky_loops);
+
+        self.interpret(program, env)
+    }
+
+    /*
+    Paths are relative to the importing file, or to the working directory for programs which do
+    not come from a file.
+     */
+    fn import(&mut self, path: &str) -> Exports {
+        let directory = match self.loading.last() {
+            Some(module) => module.parent().unwrap().to_path_buf(),
+            None => PathBuf::new(),
+        };
+        let path = directory.join(path);
+        let path = fs::canonicalize(&path)
+            .unwrap_or_else(|error| panic!("Cannot import {}: {}", path.display(), error));
+
+        match self.modules.get(&path) {
+            Some((_, exports)) => exports.clone(),
+            None => self.run_module(&path).1,
         }
+    }
 
-        Expression::Variable(name) => {
-            match env
-                .borrow()
-                .get(name)
-                .unwrap_or_else(|| panic!("Undefined variable '{}'", name))
-            {
-                EnvironmentRecord::Value(value) => value,
-                _ => panic!("Undefined variable '{}'", name),
+    fn run_module(&mut self, path: &Path) -> (Value, Exports) {
+        if let Some(start) = self.loading.iter().position(|module| module == path) {
+            let chain: Vec<String> = self.loading[start..]
+                .iter()
+                .map(|module| module.display().to_string())
+                .chain([path.display().to_string()])
+                .collect();
+
+            panic!("Circular import: {}", chain.join(" -> "));
+        }
+
+        let code = fs::read_to_string(path)
+            .unwrap_or_else(|error| panic!("Cannot read {}: {}", path.display(), error));
+        let mut program = parser::parse_source(&tokenizer::scan(&code));
+        let env = Environment::new(None);
+
+        self.loading.push(path.to_path_buf());
+        let result = self.run_program(&mut program, &env);
+        self.loading.pop();
+
+        let mut exports = HashMap::new();
+        for expression in &program.expressions {
+            if let Expression::Export { expression } = expression {
+                for name in declared_names(expression) {
+                    let record = env.borrow().get(&name).unwrap();
+                    exports.insert(name, record);
+                }
             }
         }
 
-        Expression::Yell { expression } => {
-            let value_evaluated = interpret_expression(expression, env);
-            println!("{:?}", value_evaluated);
+        self.modules
+            .insert(path.to_path_buf(), (env.clone(), exports.clone()));
 
-            Value::Null
+        (result, exports)
+    }
+
+    fn interpret(&mut self, program: &Program, env: &Rc<RefCell<Environment>>) -> Value {
+        let mut result: Value = Value::Number(0);
+
+        self.hoist_functions(&program.expressions, env);
+
+        for (index, expression) in program.expressions.iter().enumerate() {
+            result = self.interpret_statement(expression, program.positions.get(index), env)
         }
-        Expression::Block { expressions } => {
-            let mut result: Value = Value::Null;
-            let child_env = Rc::new(RefCell::new(Environment {
-                records: HashMap::new(),
-                parent: Some(env.clone()),
-            }));
 
-            for expression in expressions {
-                result = interpret_expression(expression, &child_env)
+        result
+    }
+
+    fn interpret_expression(
+        &mut self,
+        expression: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Value {
+        self.check_stack();
+
+        match expression {
+            Expression::Number(n) => Value::Number(*n),
+            Expression::Float(f) => Value::Float(*f),
+            Expression::BigInt(big) => Value::BigInt(big.clone()),
+            Expression::Decimal(decimal) => Value::Decimal(decimal.clone()),
+            Expression::Boolean(boolean) => Value::Boolean(*boolean),
+            Expression::String(string) => Value::String(string.clone()),
+            Expression::List(items) => {
+                let mut list: Vec<Value> = vec![];
+                for item in items {
+                    let item_evaluated = self.interpret_expression(item, env);
+                    list.push(item_evaluated);
+                }
+
+                Value::List(list)
             }
+            Expression::Map(entries) => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    let key_evaluated = self.interpret_expression(key, env);
+                    let value_evaluated = self.interpret_expression(value, env);
+                    map.insert(key_evaluated, value_evaluated);
+                }
 
-            result
-        }
-        Expression::If {
-            condition,
-            success_expression,
-            failure_expression,
-        } => {
-            let condition_evaluated = interpret_expression(condition, env);
+                Value::Map(map)
+            }
+            Expression::Index { target, index } => {
+                let target_evaluated = self.interpret_expression(target, env);
+                let index_evaluated = self.interpret_expression(index, env);
+
+                access(&target_evaluated, &Accessor::Index(index_evaluated))
+            }
+            Expression::IndexAssign {
+                target,
+                index,
+                value,
+            } => {
+                let (root, mut path) = self.resolve_place(target, env);
+                path.push(Accessor::Index(self.interpret_expression(index, env)));
+
+                let value_evaluated = self.interpret_expression(value, env);
+
+                let current = self.interpret_expression(root, env);
+                let updated = set_path(current, &path, value_evaluated.clone());
+                assign_place(root, updated, env);
+
+                value_evaluated
+            }
+            Expression::Null => Value::Null,
+            Expression::Binary {
+                left,
+                operation,
+                right,
+            } => {
+                let left_evaluated = self.interpret_expression(left, env);
+                let right_evaluated = self.interpret_expression(right, env);
+
+                apply_operation(left_evaluated, operation, right_evaluated)
+                    .unwrap_or_else(|error| self.runtime_error(error, expression))
+            }
+            Expression::Unary {
+                operation,
+                expression: operand,
+            } => {
+                let expression_evaluated = self.interpret_expression(operand, env);
+
+                match (operation, expression_evaluated) {
+                    (Operation::Add, Value::Number(number)) => Value::Number(number),
+                    (Operation::Subtract, Value::Number(number)) => match number.checked_neg() {
+                        Some(number) => Value::Number(number),
+                        None => Value::BigInt(-BigInt::from_i64(number)),
+                    },
+                    (Operation::Add, Value::BigInt(big)) => Value::BigInt(big),
+                    (Operation::Subtract, Value::BigInt(big)) => bigint::integer(-big),
+                    (Operation::Add, Value::Decimal(decimal)) => Value::Decimal(decimal),
+                    (Operation::Subtract, Value::Decimal(decimal)) => Value::Decimal(-decimal),
+                    (Operation::Add, Value::Float(float)) => Value::Float(float),
+                    (Operation::Subtract, Value::Float(float)) => Value::Float(-float),
+                    (Operation::Add | Operation::Subtract, _) => {
+                        panic!("Right side of numeric operation is not numeric")
+                    }
+                    _ => panic!("You can only use add an subtract for unary operators"),
+                }
+            }
+            Expression::Declare {
+                pattern,
+                value,
+                constant,
+            } => {
+                let value_evaluated = self.interpret_expression(value, env);
+
+                for (name, item) in destructure(pattern, value_evaluated.clone()) {
+                    let record = if *constant {
+                        EnvironmentRecord::Constant(item)
+                    } else {
+                        EnvironmentRecord::Value(item)
+                    };
+
+                    env.borrow_mut().declare(name, record);
+                }
+
+                value_evaluated
+            }
+            Expression::ResolvedDeclare {
+                pattern,
+                value,
+                constant,
+                slots,
+            } => {
+                let value_evaluated = self.interpret_expression(value, env);
+                let bindings = destructure(pattern, value_evaluated.clone());
+
+                for ((name, item), slot) in bindings.into_iter().zip(slots) {
+                    let record = if *constant {
+                        EnvironmentRecord::Constant(item)
+                    } else {
+                        EnvironmentRecord::Value(item)
+                    };
+
+                    env.borrow_mut().declare_slot(*slot, &name, record);
+                }
+
+                value_evaluated
+            }
+            Expression::Assign { name, value } => {
+                let value_evaluated = self.interpret_expression(value, env);
+                assign_variable(name, value_evaluated.clone(), env);
+                value_evaluated
+            }
+            Expression::ResolvedAssign {
+                name,
+                depth,
+                slot,
+                value,
+            } => {
+                let value_evaluated = self.interpret_expression(value, env);
+                env.borrow_mut().assign_slot(
+                    *depth,
+                    *slot,
+                    name,
+                    EnvironmentRecord::Value(value_evaluated.clone()),
+                );
+                value_evaluated
+            }
+            Expression::CompoundAssign {
+                target,
+                operation,
+                value,
+            } => {
+                let (place, path) = self.resolve_place(target, env);
+
+                // The right side may change the variable too, so it is only read afterwards
+                let operand = self.interpret_expression(value, env);
 
-            if let Value::Boolean(condition_evaluated_resolved) = condition_evaluated {
-                if condition_evaluated_resolved {
-                    interpret_expression(success_expression, env)
+                let root = self.interpret_expression(place, env);
+                let current = path
+                    .iter()
+                    .fold(root.clone(), |value, accessor| access(&value, accessor));
+
+                let value_evaluated = apply_operation(current, operation, operand)
+                    .unwrap_or_else(|error| self.runtime_error(error, expression));
+
+                let updated = set_path(root, &path, value_evaluated.clone());
+                assign_place(place, updated, env);
+
+                value_evaluated
+            }
+            Expression::Destructure { pattern, value } => {
+                let value_evaluated = self.interpret_expression(value, env);
+
+                for (name, item) in destructure(pattern, value_evaluated.clone()) {
+                    assign_variable(&name, item, env);
+                }
+
+                value_evaluated
+            }
+
+            Expression::Variable(name) => get_variable(name, env),
+            Expression::ResolvedVariable { name, depth, slot } => {
+                get_slot(name, *depth, *slot, env)
+            }
+
+            Expression::Yell { expression } => {
+                let value_evaluated = self.interpret_expression(expression, env);
+                println!("{:?}", value_evaluated);
+
+                Value::Null
+            }
+            Expression::Block {
+                expressions,
+                positions,
+            } => {
+                let mut result: Value = Value::Null;
+                let child_env = Environment::new(Some(env.clone()));
+
+                self.hoist_functions(expressions, &child_env);
+
+                for (index, expression) in expressions.iter().enumerate() {
+                    result = self.interpret_statement(expression, positions.get(index), &child_env)
+                }
+
+                result
+            }
+            Expression::If {
+                condition,
+                success_expression,
+                failure_expression,
+            } => {
+                if self.condition(condition, env, "if") {
+                    self.interpret_expression(success_expression, env)
                 } else if let Some(failure_expression_resolved) = failure_expression {
-                    interpret_expression(failure_expression_resolved, env)
+                    self.interpret_expression(failure_expression_resolved, env)
                 } else {
                     Value::Null
                 }
-            } else {
-                panic!("If statements need to evaluate to a boolean")
             }
-        }
-        Expression::Comparison {
-            left,
-            comparator,
-            right,
-        } => {
-            let left_evaluated = interpret_expression(left, env);
-            let right_evaluated = interpret_expression(right, env);
-
-            match comparator {
-                Comparator::Equality => Value::Boolean(left_evaluated == right_evaluated),
-                Comparator::GreaterThan => match (left_evaluated, right_evaluated) {
-                    (Value::Number(left), Value::Number(right)) => Value::Boolean(left > right),
-                    (Value::Number(_), _) => {
-                        panic!("Right side of greater than comparison needs to be a number")
+            Expression::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                let mut bound =
+                    |expression: &Expression| match self.interpret_expression(expression, env) {
+                        Value::Number(number) => number,
+                        value => panic!("Ranges can only be built from numbers, got {:?}", value),
+                    };
+
+                let start = bound(start);
+                let end = bound(end);
+                let step = step.as_deref().map(&mut bound).unwrap_or(1);
+
+                Value::Range(Range::new(start, end, step, *inclusive))
+            }
+            // Both operators short circuit, the right side is only evaluated when it is needed
+            Expression::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let context = match operator {
+                    Logical::And => "&&",
+                    Logical::Or => "||",
+                };
+
+                Value::Boolean(match operator {
+                    Logical::And => {
+                        self.condition(left, env, context) && self.condition(right, env, context)
                     }
-                    (_, _) => {
-                        panic!("Left side of greater than comparison needs to be a number")
+                    Logical::Or => {
+                        self.condition(left, env, context) || self.condition(right, env, context)
                     }
-                },
-                Comparator::LessThan => match (left_evaluated, right_evaluated) {
-                    (Value::Number(left), Value::Number(right)) => Value::Boolean(left < right),
-                    (Value::Number(_), _) => {
-                        panic!("Right side of less than comparison needs to be a number")
+                })
+            }
+            Expression::Not {
+                expression: operand,
+            } => Value::Boolean(!self.condition(operand, env, "!")),
+            Expression::Comparison {
+                left,
+                comparator,
+                right,
+            } => {
+                let left_evaluated = self.interpret_expression(left, env);
+                let right_evaluated = self.interpret_expression(right, env);
+
+                match comparator {
+                    Comparator::Equality => {
+                        Value::Boolean(values_equal(&left_evaluated, &right_evaluated))
                     }
-                    (_, _) => {
-                        panic!("Left side of less than comparison needs to be a number")
+                    Comparator::GreaterThan => Value::Boolean(
+                        compare_values(&left_evaluated, &right_evaluated, "greater than")
+                            == Some(Ordering::Greater),
+                    ),
+                    Comparator::LessThan => Value::Boolean(
+                        compare_values(&left_evaluated, &right_evaluated, "less than")
+                            == Some(Ordering::Less),
+                    ),
+                    Comparator::In => {
+                        Value::Boolean(contains_value(&right_evaluated, &left_evaluated))
                     }
-                },
+                }
             }
-        }
-        Expression::While {
-            condition,
-            expression,
-        } => {
-            let mut condition_evaluated = interpret_expression(condition, env);
-            let mut continue_loop = is_truthy_value(condition_evaluated);
+            Expression::While {
+                condition,
+                expression,
+            } => {
+                while self.condition(condition, env, "while") {
+                    self.interpret_expression(expression, env);
+                }
+
+                Value::Null
+            }
+            Expression::For {
+                pattern,
+                list,
+                expression,
+            } => {
+                match self.interpret_expression(list, env) {
+                    Value::List(list_evaluated) => {
+                        for item in list_evaluated {
+                            self.iterate(pattern, item, expression, env);
+                        }
+                    }
+                    Value::Map(map) => {
+                        for (key, value) in map.entries() {
+                            let entry = Value::List(vec![key.clone(), value.clone()]);
+                            self.iterate(pattern, entry, expression, env);
+                        }
+                    }
+                    Value::Range(range) => {
+                        for number in range.iter() {
+                            self.iterate(pattern, Value::Number(number), expression, env);
+                        }
+                    }
+                    _ => panic!("for loop needs to iterate over a list, a map or a range"),
+                }
 
-            while continue_loop {
-                interpret_expression(expression, env);
+                Value::Null
+            }
+            Expression::Function {
+                identifier,
+                parameters,
+                expression,
+            } => {
+                env.borrow_mut().declare(
+                    identifier.clone(),
+                    EnvironmentRecord::Function(FunctionDefinition {
+                        parameters: Rc::new(parameters.clone()),
+                        expression: Rc::new(expression.as_ref().clone()),
+                        closure: Closure::Weak(Rc::downgrade(env)),
+                    }),
+                );
+
+                Value::Null
+            }
+            Expression::FunctionCall {
+                identifier,
+                parameters,
+            } => {
+                let function = get_variable(identifier, env);
+                let arguments = self.evaluate_arguments(parameters, env);
+
+                self.call_value(&function, arguments)
+            }
+            Expression::ResolvedFunctionCall {
+                identifier,
+                depth,
+                slot,
+                parameters,
+            } => {
+                let function = get_slot(identifier, *depth, *slot, env);
+                let arguments = self.evaluate_arguments(parameters, env);
+
+                self.call_value(&function, arguments)
+            }
+            Expression::Struct { identifier, fields } => {
+                env.borrow_mut().declare(
+                    identifier.clone(),
+                    EnvironmentRecord::Struct(Rc::new(StructDefinition {
+                        fields: fields.clone(),
+                        methods: RefCell::new(HashMap::new()),
+                    })),
+                );
+
+                Value::Null
+            }
+            Expression::Impl {
+                identifier,
+                methods,
+            } => {
+                let definition = get_struct(identifier, env);
+                // Methods are stored in the struct, weakly referring to its scope if they share it
+                let closure = if env.borrow().declares(identifier) {
+                    Closure::Weak(Rc::downgrade(env))
+                } else {
+                    Closure::Strong(env.clone())
+                };
+
+                for method in methods {
+                    if let Expression::Function {
+                        identifier,
+                        parameters,
+                        expression,
+                    } = method
+                    {
+                        definition.methods.borrow_mut().insert(
+                            identifier.clone(),
+                            FunctionDefinition {
+                                parameters: Rc::new(parameters.clone()),
+                                expression: Rc::new(expression.as_ref().clone()),
+                                closure: closure.clone(),
+                            },
+                        );
+                    }
+                }
 
-                condition_evaluated = interpret_expression(condition, env);
-                continue_loop = is_truthy_value(condition_evaluated);
+                Value::Null
             }
+            Expression::StructInstance { identifier, fields } => {
+                let definition = get_struct(identifier, env);
 
-            Value::Null
-        }
-        Expression::For {
-            identifier,
-            list,
-            expression,
-        } => {
-            match interpret_expression(list, env) {
-                Value::List(list_evaluated) => {
-                    for item in list_evaluated {
-                        env.borrow_mut()
-                            .set(identifier.clone(), EnvironmentRecord::Value(item));
-                        interpret_expression(expression, env);
+                for (field, _) in fields {
+                    if !definition.fields.contains(field) {
+                        panic!("Struct {} does not have a field '{}'", identifier, field);
                     }
                 }
-                _ => panic!("for loop needs to iterate over a list"),
+
+                let mut instance = Instance {
+                    identifier: identifier.clone(),
+                    fields: vec![],
+                    definition: definition.clone(),
+                    _scope: Environment::scope_of(env, identifier).unwrap(),
+                };
+
+                for field in &definition.fields {
+                    let value = fields
+                        .iter()
+                        .find(|(name, _)| name == field)
+                        .map(|(_, value)| value)
+                        .unwrap_or_else(|| {
+                            panic!("Missing field '{}' for struct {}", field, identifier)
+                        });
+
+                    instance
+                        .fields
+                        .push((field.clone(), self.interpret_expression(value, env)));
+                }
+
+                Value::Struct(instance)
             }
+            Expression::Field { target, field } => {
+                let target_evaluated = self.interpret_expression(target, env);
+                access(&target_evaluated, &Accessor::Field(field.clone()))
+            }
+            Expression::FieldAssign {
+                target,
+                field,
+                value,
+            } => {
+                let (root, mut path) = self.resolve_place(target, env);
+                path.push(Accessor::Field(field.clone()));
 
-            Value::Null
+                let value_evaluated = self.interpret_expression(value, env);
+
+                let current = self.interpret_expression(root, env);
+                let updated = set_path(current, &path, value_evaluated.clone());
+                assign_place(root, updated, env);
+
+                value_evaluated
+            }
+            Expression::MethodCall {
+                target,
+                method,
+                parameters,
+            } => {
+                let receiver = self.interpret_expression(target, env);
+                let mut arguments = self.evaluate_arguments(parameters, env);
+                arguments.positional.insert(0, receiver.clone());
+
+                if let Value::Struct(instance) = &receiver {
+                    let function = instance.definition.methods.borrow().get(method).cloned();
+
+                    if let Some(function) = function {
+                        return self.call_function(method, &function, arguments);
+                    }
+                }
+
+                if let Some(builtin) = builtins::get_method(&receiver, method) {
+                    return self.call_builtin(method, builtin, arguments);
+                }
+
+                // Any other function can be called as a method of its first argument
+                match lookup_variable(method, env) {
+                    Some(function @ Value::Function(_)) => self.call_value(&function, arguments),
+                    _ => match &receiver {
+                        Value::Struct(instance) => panic!(
+                            "Struct {} does not have a method '{}'",
+                            instance.identifier, method
+                        ),
+                        _ => panic!("Cannot call method {} on {:?}", method, receiver),
+                    },
+                }
+            }
+            Expression::Pipeline {
+                value,
+                identifier,
+                parameters,
+            } => {
+                let value = self.interpret_expression(value, env);
+                let function = get_variable(identifier, env);
+
+                let mut arguments = self.evaluate_arguments(parameters, env);
+                arguments.positional.insert(0, value);
+
+                self.call_value(&function, arguments)
+            }
+            Expression::ResolvedPipeline {
+                value,
+                identifier,
+                depth,
+                slot,
+                parameters,
+            } => {
+                let value = self.interpret_expression(value, env);
+                let function = get_slot(identifier, *depth, *slot, env);
+
+                let mut arguments = self.evaluate_arguments(parameters, env);
+                arguments.positional.insert(0, value);
+
+                self.call_value(&function, arguments)
+            }
+            Expression::Enum {
+                identifier,
+                variants,
+            } => {
+                env.borrow_mut().declare(
+                    identifier.clone(),
+                    EnvironmentRecord::Enum(EnumDefinition {
+                        variants: variants.clone(),
+                    }),
+                );
+
+                Value::Null
+            }
+            Expression::Variant {
+                identifier,
+                variant,
+                parameters,
+            } => {
+                check_variant(identifier, variant, parameters.len(), env);
+
+                Value::Variant(Variant {
+                    identifier: identifier.clone(),
+                    variant: variant.clone(),
+                    payload: parameters
+                        .iter()
+                        .map(|parameter| self.interpret_expression(parameter, env))
+                        .collect(),
+                })
+            }
+            Expression::Match { value, arms } => {
+                let value_evaluated = self.interpret_expression(value, env);
+
+                for arm in arms {
+                    check_pattern(&arm.pattern, env);
+                }
+
+                for arm in arms {
+                    let Ok(bindings) = match_pattern(&arm.pattern, value_evaluated.clone()) else {
+                        continue;
+                    };
+
+                    let arm_env = Environment::new(Some(env.clone()));
+
+                    for (name, value) in bindings {
+                        arm_env
+                            .borrow_mut()
+                            .declare(name, EnvironmentRecord::Value(value));
+                    }
+
+                    if let Some(guard) = &arm.guard
+                        && !self.condition(guard, &arm_env, "a match guard")
+                    {
+                        continue;
+                    }
+
+                    return self.interpret_expression(&arm.expression, &arm_env);
+                }
+
+                panic!(
+                    "No arm of the match expression matched {:?}",
+                    value_evaluated
+                )
+            }
+            Expression::Prompt => {
+                let mut input_string = String::new();
+
+                stdin()
+                    .read_line(&mut input_string)
+                    .expect("Failed to read input");
+
+                Value::String(input_string)
+            }
+            // Imports are loaded before the program runs
+            Expression::Import { .. } => Value::Null,
+            Expression::Export { expression } => self.interpret_expression(expression, env),
         }
-        Expression::Function {
-            identifier,
-            parameters,
-            expression,
-        } => {
-            env.borrow_mut().set(
-                identifier.clone(),
-                EnvironmentRecord::Function {
-                    parameters: parameters.clone(),
-                    expression: expression.clone(),
+    }
+
+    /*
+    Declares every function of a block before the block runs, so functions can be called before
+    their definition and can call each other no matter in which order they were written.
+     */
+    fn hoist_functions(&mut self, expressions: &[Expression], env: &Rc<RefCell<Environment>>) {
+        for expression in expressions {
+            let declaration = match expression {
+                Expression::Export { expression } => expression,
+                expression => expression,
+            };
+
+            if let Expression::Function { .. } = declaration {
+                self.interpret_expression(declaration, env);
+            }
+        }
+    }
+
+    fn evaluate_arguments(
+        &mut self,
+        arguments: &[Argument],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Arguments {
+        let mut evaluated = Arguments {
+            positional: vec![],
+            named: vec![],
+        };
+
+        for argument in arguments {
+            if !evaluated.named.is_empty() && !matches!(argument, Argument::Named(..)) {
+                panic!("Positional arguments can't follow named arguments");
+            }
+
+            match argument {
+                Argument::Positional(expression) => {
+                    evaluated
+                        .positional
+                        .push(self.interpret_expression(expression, env));
+                }
+                Argument::Named(name, expression) => {
+                    evaluated
+                        .named
+                        .push((name.clone(), self.interpret_expression(expression, env)));
+                }
+                Argument::Spread(expression) => match self.interpret_expression(expression, env) {
+                    Value::List(list) => evaluated.positional.extend(list),
+                    Value::Range(range) => {
+                        evaluated.positional.extend(range.iter().map(Value::Number))
+                    }
+                    value => panic!(
+                        "Only lists and ranges can be spread into arguments, got {:?}",
+                        value
+                    ),
                 },
+            }
+        }
+
+        evaluated
+    }
+
+    fn call_value(&mut self, function: &Value, arguments: Arguments) -> Value {
+        match function {
+            Value::Function(Function::Defined {
+                identifier,
+                definition,
+            }) => self.call_function(identifier, definition, arguments),
+            Value::Function(Function::Builtin(identifier)) => self.call_builtin(
+                identifier,
+                builtins::get_builtin(identifier).unwrap(),
+                arguments,
+            ),
+            value => panic!("Cannot call {:?}, it is not a function", value),
+        }
+    }
+
+    fn call_builtin(&mut self, identifier: &str, builtin: Builtin, arguments: Arguments) -> Value {
+        if !arguments.named.is_empty() {
+            panic!("Builtin {} does not take named arguments", identifier);
+        }
+
+        match builtin {
+            Builtin::Plain(builtin) => builtin(arguments.positional),
+            Builtin::HigherOrder(builtin) => {
+                builtin(arguments.positional, &mut |function, positional| {
+                    self.call_value(
+                        function,
+                        Arguments {
+                            positional,
+                            named: vec![],
+                        },
+                    )
+                })
+            }
+        }
+    }
+
+    /*
+    The body of a function runs in a new scope whose parent is the scope the function was defined
+    in, never the one it is called from.
+
+    Positional arguments fill the parameters from left to right and named arguments the parameter
+    with their name. Whatever is still missing afterwards falls back to its default value, which is
+    evaluated inside the function so it can refer to the parameters before it. Surplus positional
+    arguments end up in the rest parameter if there is one.
+     */
+    fn call_function(
+        &mut self,
+        identifier: &str,
+        function: &FunctionDefinition,
+        arguments: Arguments,
+    ) -> Value {
+        if self.calls.len() == self.options.max_call_depth {
+            panic!(
+                "Maximum recursion depth exceeded after {} nested calls: {}",
+                self.calls.len(),
+                call_chain(&self.calls)
             );
+        }
 
-            Value::Null
+        self.calls.push(identifier.to_string());
+
+        let (variadic, parameters): (Vec<&Parameter>, Vec<&Parameter>) = function
+            .parameters
+            .iter()
+            .partition(|parameter| parameter.variadic);
+
+        let Arguments {
+            mut positional,
+            named,
+        } = arguments;
+
+        if positional.len() > parameters.len() && variadic.is_empty() {
+            let required = parameters
+                .iter()
+                .filter(|parameter| parameter.default.is_none())
+                .count();
+
+            let expected = if required == parameters.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, parameters.len())
+            };
+
+            panic!(
+                "Function {} takes {} arguments but got {}",
+                identifier,
+                expected,
+                positional.len() + named.len()
+            );
         }
-        Expression::FunctionCall {
-            identifier,
-            parameters,
-        } => {
-            match env
-                .borrow()
-                .get(identifier)
-                .unwrap_or_else(|| panic!("Undefined variable '{}'", identifier))
-            {
-                EnvironmentRecord::Function {
-                    parameters: parameter_names,
-                    expression,
-                } => {
-                    if parameters.len() != parameter_names.len() {
-                        panic!(
-                            "Incorrect amount of parameters supplied for function {}",
-                            identifier
-                        );
-                    }
 
-                    let child_env = Rc::new(RefCell::new(Environment {
-                        records: HashMap::new(),
-                        parent: Some(env.clone()),
-                    }));
+        let rest = positional.split_off(positional.len().min(parameters.len()));
+
+        let mut values: Vec<Option<Value>> = positional.into_iter().map(Some).collect();
+        values.resize(parameters.len(), None);
+
+        for (name, value) in named {
+            let position = parameters
+                .iter()
+                .position(|parameter| parameter.pattern == Pattern::Identifier(name.clone()))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Function {} does not have a parameter named '{}'",
+                        identifier, name
+                    )
+                });
+
+            if values[position].is_some() {
+                panic!(
+                    "Parameter '{}' of function {} was given more than once",
+                    name, identifier
+                );
+            }
 
-                    let mut i = 0;
-                    while i < parameters.len() {
-                        let parameter = &parameters[i];
-                        let parameter_name = &parameter_names[i];
-                        let parameter_resolved = interpret_expression(parameter, env);
+            values[position] = Some(value);
+        }
 
-                        child_env.borrow_mut().records.insert(
-                            parameter_name.clone(),
-                            EnvironmentRecord::Value(parameter_resolved),
-                        );
+        let child_env = Environment::new(Some(function.closure.env()));
+
+        for (position, (parameter, value)) in parameters.into_iter().zip(values).enumerate() {
+            let value = match (value, &parameter.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.interpret_expression(default, &child_env),
+                (None, None) => match &parameter.pattern {
+                    Pattern::Identifier(name) => panic!(
+                        "Function {} is missing the argument for parameter '{}'",
+                        identifier, name
+                    ),
+                    _ => panic!(
+                        "Function {} is missing the argument for parameter {}",
+                        identifier,
+                        position + 1
+                    ),
+                },
+            };
 
-                        i += 1;
-                    }
+            self.bind_parameter(parameter, value, &child_env);
+        }
 
-                    interpret_expression(&*expression, &child_env)
-                }
-                _ => panic!("Undefined variable '{}'", identifier),
+        if let Some(parameter) = variadic.first() {
+            self.bind_parameter(parameter, Value::List(rest), &child_env);
+        }
+
+        let result = self.interpret_expression(&function.expression, &child_env);
+        self.calls.pop();
+
+        result
+    }
+
+    fn bind_parameter(
+        &mut self,
+        parameter: &Parameter,
+        value: Value,
+        env: &Rc<RefCell<Environment>>,
+    ) {
+        for (name, value) in destructure(&parameter.pattern, value) {
+            env.borrow_mut()
+                .declare(name, EnvironmentRecord::Value(value));
+        }
+    }
+
+    /*
+    Resolves the target of an assignment into the variable it is stored in and the already
+    evaluated accessors leading to it, so every index expression is only evaluated once.
+     */
+    fn resolve_place<'a>(
+        &mut self,
+        expression: &'a Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> (&'a Expression, Vec<Accessor>) {
+        match expression {
+            Expression::Variable(_) | Expression::ResolvedVariable { .. } => (expression, vec![]),
+            Expression::Index { target, index } => {
+                let (root, mut path) = self.resolve_place(target, env);
+                path.push(Accessor::Index(self.interpret_expression(index, env)));
+                (root, path)
             }
+            Expression::Field { target, field } => {
+                let (root, mut path) = self.resolve_place(target, env);
+                path.push(Accessor::Field(field.clone()));
+                (root, path)
+            }
+            _ => panic!("Cannot assign to {:?}", expression),
         }
-        Expression::Prompt => {
-            let mut input_string = String::new();
+    }
+}
+
+/*
+The address of a local variable, which tells how deep the stack currently is
+ */
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+/*
+Describes a chain of calls with the cycle that keeps repeating at its end written only once, like
+outer -> even -> (odd -> even) repeated 499 times
+ */
+fn call_chain(calls: &[String]) -> String {
+    let Some((last, before)) = calls.split_last() else {
+        return String::new();
+    };
+
+    let cycle = match before.iter().rposition(|call| call == last) {
+        Some(position) => before.len() - position,
+        None => calls.len(),
+    };
+
+    let mut start = calls.len() - cycle;
+    let mut repeats = 1;
+    while start >= cycle && calls[start - cycle..start] == calls[start..start + cycle] {
+        start -= cycle;
+        repeats += 1;
+    }
+
+    let mut parts = calls[..start].to_vec();
+    let cycle = calls[start..start + cycle].join(" -> ");
 
-            stdin()
-                .read_line(&mut input_string)
-                .expect("Failed to read input");
+    parts.push(match (repeats, cycle.contains(" -> ")) {
+        (1, _) => cycle,
+        (_, false) => format!("{} repeated {} times", cycle, repeats),
+        (_, true) => format!("({}) repeated {} times", cycle, repeats),
+    });
 
-            Value::String(input_string)
+    parts.join(" -> ")
+}
+
+/*
+The names an exported declaration binds at the top level of its module
+ */
+fn declared_names(expression: &Expression) -> Vec<String> {
+    match expression {
+        Expression::Function { identifier, .. }
+        | Expression::Struct { identifier, .. }
+        | Expression::Enum { identifier, .. } => vec![identifier.clone()],
+        Expression::Declare { pattern, .. } | Expression::ResolvedDeclare { pattern, .. } => {
+            resolver::pattern_names(pattern)
         }
+        _ => unreachable!(),
     }
 }
 
-fn is_truthy_value(value: Value) -> bool {
-    match value {
-        Value::Number(number) => number > 0,
-        Value::Float(float) => float > 0.0,
-        Value::Boolean(bool) => bool,
-        Value::String(string) => string != "",
-        Value::Null => false,
-        Value::List(list) => list.len() > 0,
+fn get_variable(name: &str, env: &Rc<RefCell<Environment>>) -> Value {
+    lookup_variable(name, env).unwrap_or_else(|| panic!("Undefined variable '{}'", name))
+}
+
+/*
+The value in the slot the resolver found for the name
+ */
+fn get_slot(name: &str, depth: usize, slot: usize, env: &Rc<RefCell<Environment>>) -> Value {
+    match env.borrow().get_slot(depth, slot) {
+        Some(record) => {
+            record_value(name, record).unwrap_or_else(|| panic!("Undefined variable '{}'", name))
+        }
+        None => panic!("Variable '{}' is used before its declaration", name),
     }
 }
 
-#[cfg(test)]
-mod tests {
+/*
+Functions, including builtins that have not been overridden, can be used as values too
+ */
+fn lookup_variable(name: &str, env: &Rc<RefCell<Environment>>) -> Option<Value> {
+    match env.borrow().get(name) {
+        Some(record) => record_value(name, record),
+        None if builtins::get_builtin(name).is_some() => {
+            Some(Value::Function(Function::Builtin(name.to_string())))
+        }
+        None => None,
+    }
+}
+
+/*
+Structs and enums are no values, only their instances and variants are
+ */
+fn record_value(name: &str, record: EnvironmentRecord) -> Option<Value> {
+    match record {
+        EnvironmentRecord::Value(value) | EnvironmentRecord::Constant(value) => Some(value),
+        EnvironmentRecord::Function(definition) => Some(Value::Function(Function::Defined {
+            identifier: name.to_string(),
+            definition: definition.strong(),
+        })),
+        EnvironmentRecord::Struct(_) | EnvironmentRecord::Enum(_) => None,
+    }
+}
+
+fn assign_variable(name: &str, value: Value, env: &Rc<RefCell<Environment>>) {
+    env.borrow_mut()
+        .assign(name, EnvironmentRecord::Value(value));
+}
+
+/*
+Stores the updated value of a variable whose item or field was assigned to
+ */
+fn assign_place(place: &Expression, value: Value, env: &Rc<RefCell<Environment>>) {
+    match place {
+        Expression::ResolvedVariable { name, depth, slot } => {
+            env.borrow_mut()
+                .assign_slot(*depth, *slot, name, EnvironmentRecord::Value(value))
+        }
+        Expression::Variable(name) => assign_variable(name, value, env),
+        _ => unreachable!(),
+    }
+}
+
+/*
+Integers stay integers as long as both sides are integers. As soon as a float is involved the
+integer is promoted to a float, so 5.5 + 1 is 6.5. Float arithmetic follows IEEE 754: dividing a
+float by zero gives infinity and 0.0 / 0.0 gives NaN.
+
+Integer arithmetic is checked, so it behaves the same in debug and release builds. Results which
+don't fit into an i64 become big integers and dividing by zero is an error, which the caller
+turns into a panic as it knows the expression that failed.
+
+Decimals stay exact, integers are promoted to decimals when combined with one. Decimals and
+floats don't mix, as the result could be neither exact nor a float on purpose.
+
+Strings and lists can be concatenated with + and repeated a number of times with *.
+ */
+fn apply_operation(
+    left: Value,
+    operation: &Operation,
+    right: Value,
+) -> Result<Value, &'static str> {
+    let value = match (left, operation, right) {
+        (Value::Number(left), _, Value::Number(right)) => {
+            let result = match operation {
+                Operation::Divide | Operation::Modulo if right == 0 => {
+                    return Err("Division by zero");
+                }
+                Operation::Add => left.checked_add(right),
+                Operation::Subtract => left.checked_sub(right),
+                Operation::Multiply => left.checked_mul(right),
+                Operation::Divide => left.checked_div(right),
+                Operation::Modulo => left.checked_rem(right),
+            };
+
+            match result {
+                Some(number) => Value::Number(number),
+                None => {
+                    apply_big_operation(BigInt::from_i64(left), operation, BigInt::from_i64(right))?
+                }
+            }
+        }
+        (
+            left @ (Value::Number(_) | Value::BigInt(_)),
+            _,
+            right @ (Value::Number(_) | Value::BigInt(_)),
+        ) => apply_big_operation(as_big(&left).unwrap(), operation, as_big(&right).unwrap())?,
+        (
+            left @ (Value::Number(_) | Value::BigInt(_) | Value::Decimal(_)),
+            _,
+            right @ (Value::Number(_) | Value::BigInt(_) | Value::Decimal(_)),
+        ) => apply_decimal_operation(
+            as_decimal(&left).unwrap(),
+            operation,
+            as_decimal(&right).unwrap(),
+        )?,
+        (left @ Value::Decimal(_), _, right @ Value::Float(_))
+        | (left @ Value::Float(_), _, right @ Value::Decimal(_)) => panic!(
+            "Cannot apply {:?} to {:?} and {:?}, convert one of them with decimal or float first",
+            operation, left, right
+        ),
+        (Value::String(left), Operation::Add, Value::String(right)) => Value::String(left + &right),
+        (Value::List(mut left), Operation::Add, Value::List(right)) => {
+            left.extend(right);
+            Value::List(left)
+        }
+        (Value::String(string), Operation::Multiply, Value::Number(times))
+        | (Value::Number(times), Operation::Multiply, Value::String(string)) => {
+            let times = repetitions(times);
+            repeated_length(string.len(), times, 1)?;
+            Value::String(string.repeat(times))
+        }
+        (Value::List(list), Operation::Multiply, Value::Number(times))
+        | (Value::Number(times), Operation::Multiply, Value::List(list)) => {
+            let length = repeated_length(list.len(), repetitions(times), size_of::<Value>())?;
+            Value::List(list.iter().cycle().take(length).cloned().collect())
+        }
+        (left, operation, right) => match (as_float(&left), as_float(&right)) {
+            (Some(left), Some(right)) => Value::Float(match operation {
+                Operation::Add => left + right,
+                Operation::Subtract => left - right,
+                Operation::Multiply => left * right,
+                Operation::Divide => left / right,
+                Operation::Modulo => left % right,
+            }),
+            _ => panic!("Cannot apply {:?} to {:?} and {:?}", operation, left, right),
+        },
+    };
+
+    Ok(value)
+}
+
+fn repetitions(times: i64) -> usize {
+    usize::try_from(times).unwrap_or_else(|_| panic!("Cannot repeat something {} times", times))
+}
+
+/*
+The number of items in something repeated a number of times. All of them have to fit into a
+single allocation, which can't be larger than isize::MAX bytes.
+ */
+fn repeated_length(length: usize, times: usize, item_size: usize) -> Result<usize, &'static str> {
+    length
+        .checked_mul(times)
+        .filter(|total| {
+            total
+                .checked_mul(item_size)
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        })
+        .ok_or("Repetition too large")
+}
+
+/*
+Big integers are turned back into numbers as soon as they fit into an i64 again
+ */
+fn apply_big_operation(
+    left: BigInt,
+    operation: &Operation,
+    right: BigInt,
+) -> Result<Value, &'static str> {
+    let result = match operation {
+        Operation::Divide | Operation::Modulo if right.is_zero() => {
+            return Err("Division by zero");
+        }
+        Operation::Add => left + right,
+        Operation::Subtract => left - right,
+        Operation::Multiply => left * right,
+        Operation::Divide => left.div_rem(&right).0,
+        Operation::Modulo => left.div_rem(&right).1,
+    };
+
+    Ok(bigint::integer(result))
+}
+
+fn apply_decimal_operation(
+    left: Decimal,
+    operation: &Operation,
+    right: Decimal,
+) -> Result<Value, &'static str> {
+    let result = match operation {
+        Operation::Divide | Operation::Modulo if right.is_zero() => {
+            return Err("Division by zero");
+        }
+        Operation::Add => left + right,
+        Operation::Subtract => left - right,
+        Operation::Multiply => left * right,
+        Operation::Divide => left.divide_default(&right),
+        Operation::Modulo => left.remainder(&right),
+    };
+
+    Ok(Value::Decimal(result))
+}
+
+fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => Some(*number as f64),
+        Value::Float(float) => Some(*float),
+        Value::BigInt(big) => Some(big.to_f64()),
+        Value::Decimal(decimal) => Some(decimal.to_f64()),
+        _ => None,
+    }
+}
+
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Decimal(decimal) => Some(decimal.clone()),
+        value => as_big(value).map(Decimal::from_integer),
+    }
+}
+
+fn as_big(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(number) => Some(BigInt::from_i64(*number)),
+        Value::BigInt(big) => Some(big.clone()),
+        _ => None,
+    }
+}
+
+/*
+Integers are compared with each other exactly and promoted to floats when compared with a float.
+NaN is not ordered, so every comparison with it is false. Strings and lists are compared
+lexicographically, lists item by item.
+ */
+fn compare_values(left: &Value, right: &Value, comparison: &str) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => Some(left.cmp(right)),
+        (Value::Number(_) | Value::BigInt(_), Value::BigInt(_))
+        | (Value::BigInt(_), Value::Number(_)) => as_big(left).partial_cmp(&as_big(right)),
+        (Value::Number(_) | Value::BigInt(_) | Value::Decimal(_), Value::Decimal(_))
+        | (Value::Decimal(_), Value::Number(_) | Value::BigInt(_)) => {
+            as_decimal(left).partial_cmp(&as_decimal(right))
+        }
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::List(left), Value::List(right)) => {
+            for (left, right) in left.iter().zip(right) {
+                match compare_values(left, right, comparison) {
+                    Some(Ordering::Equal) => continue,
+                    ordering => return ordering,
+                }
+            }
+
+            Some(left.len().cmp(&right.len()))
+        }
+        _ => match (as_float(left), as_float(right)) {
+            (Some(left), Some(right)) => left.partial_cmp(&right),
+            _ => panic!(
+                "Cannot use {} comparison on {:?} and {:?}",
+                comparison, left, right
+            ),
+        },
+    }
+}
+
+/*
+Numbers are equal when they have the same value, whatever kind of number they are, so 5 == 5.0
+and 5 == 5.00d. NaN is not equal to anything, not even to itself. Lists are equal when all their
+items are. Maps, struct instances and enum variants compare what they contain the same way, and
+so do map keys and contains, so there is only one notion of equality.
+ */
+pub fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (left, right) if as_float(left).is_some() && as_float(right).is_some() => {
+            compare_values(left, right, "equality") == Some(Ordering::Equal)
+        }
+        (Value::List(left), Value::List(right)) => {
+            left.len() == right.len()
+                && left
+                    .iter()
+                    .zip(right)
+                    .all(|(left, right)| values_equal(left, right))
+        }
+        _ => left == right,
+    }
+}
+
+/*
+Whether an item is in a list or range, a key in a map or a part of a string
+ */
+fn contains_value(collection: &Value, item: &Value) -> bool {
+    match (collection, item) {
+        (Value::List(list), item) => list.iter().any(|value| values_equal(value, item)),
+        (Value::Range(range), Value::Number(number)) => range.contains(*number),
+        (Value::Range(_), _) => false,
+        (Value::Map(map), key) => map.has(key),
+        (Value::String(string), Value::String(part)) => string.contains(part.as_str()),
+        (collection, item) => panic!("Cannot check whether {:?} is in {:?}", item, collection),
+    }
+}
+
+fn get_struct(name: &str, env: &Rc<RefCell<Environment>>) -> Rc<StructDefinition> {
+    match env.borrow().get(name) {
+        Some(EnvironmentRecord::Struct(definition)) => definition,
+        _ => panic!("Undefined struct '{}'", name),
+    }
+}
+
+/*
+Makes sure the enum has the variant and that it is given as many values as the variant has
+ */
+fn check_variant(identifier: &str, variant: &str, values: usize, env: &Rc<RefCell<Environment>>) {
+    let definition = match env.borrow().get(identifier) {
+        Some(EnvironmentRecord::Enum(definition)) => definition,
+        _ => panic!("Undefined enum '{}'", identifier),
+    };
+
+    let (_, payload_names) = definition
+        .variants
+        .iter()
+        .find(|(name, _)| name == variant)
+        .unwrap_or_else(|| panic!("Enum {} does not have a variant '{}'", identifier, variant));
+
+    if values != payload_names.len() {
+        panic!(
+            "Variant {}::{} expects {} values but got {}",
+            identifier,
+            variant,
+            payload_names.len(),
+            values
+        );
+    }
+}
+
+/*
+A variant pattern that doesn't fit its enum could never match, so it is an error instead of an
+arm that is silently skipped
+ */
+fn check_pattern(pattern: &Pattern, env: &Rc<RefCell<Environment>>) {
+    match pattern {
+        Pattern::Variant {
+            identifier,
+            variant,
+            payload,
+        } => {
+            check_variant(identifier, variant, payload.len(), env);
+
+            for item in payload {
+                check_pattern(item, env);
+            }
+        }
+        Pattern::List { items, .. } => {
+            for item in items {
+                check_pattern(item, env);
+            }
+        }
+        Pattern::Identifier(_) | Pattern::Wildcard | Pattern::Literal(_) => {}
+    }
+}
+
+/*
+Call arguments after evaluation. Spread arguments have already been expanded into positional
+ones at this point.
+ */
+struct Arguments {
+    positional: Vec<Value>,
+    named: Vec<(String, Value)>,
+}
+
+/*
+A single step from a value into one of its parts
+ */
+enum Accessor {
+    Index(Value),
+    Field(String),
+}
+
+fn access(target: &Value, accessor: &Accessor) -> Value {
+    match (target, accessor) {
+        (Value::Range(range), Accessor::Index(Value::Range(indices))) => {
+            Value::Range(range.slice(indices))
+        }
+        (Value::List(list), Accessor::Index(Value::Range(indices))) => Value::List(
+            indices
+                .iter()
+                .map(|position| list[checked_position(position, list.len())].clone())
+                .collect(),
+        ),
+        (Value::String(string), Accessor::Index(Value::Range(indices))) => {
+            let characters: Vec<char> = string.chars().collect();
+            Value::String(
+                indices
+                    .iter()
+                    .map(|position| characters[checked_position(position, characters.len())])
+                    .collect(),
+            )
+        }
+        (Value::Range(range), Accessor::Index(Value::Number(position))) => {
+            Value::Number(range.get(checked_position(*position, range.len())).unwrap())
+        }
+        (Value::List(list), Accessor::Index(Value::Number(position))) => list
+            .get(checked_position(*position, list.len()))
+            .cloned()
+            .unwrap(),
+        (Value::String(string), Accessor::Index(Value::Number(position))) => {
+            let characters: Vec<char> = string.chars().collect();
+            let character = characters[checked_position(*position, characters.len())];
+            Value::String(character.to_string())
+        }
+        (Value::Map(map), Accessor::Index(key)) => map
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| panic!("Key {:?} does not exist in map", key)),
+        (Value::Struct(instance), Accessor::Field(field)) => {
+            instance.get(field).cloned().unwrap_or_else(|| {
+                panic!(
+                    "Struct {} does not have a field '{}'",
+                    instance.identifier, field
+                )
+            })
+        }
+        (_, Accessor::Index(index)) => panic!("Cannot index {:?} with {:?}", target, index),
+        (_, Accessor::Field(field)) => {
+            panic!("Cannot access field '{}' of {:?}", field, target)
+        }
+    }
+}
+
+/*
+Returns a copy of the target where the value at the end of the path has been replaced
+ */
+fn set_path(target: Value, path: &[Accessor], value: Value) -> Value {
+    let Some((accessor, rest)) = path.split_first() else {
+        return value;
+    };
+
+    let value = if rest.is_empty() {
+        value
+    } else {
+        set_path(access(&target, accessor), rest, value)
+    };
+
+    match (target, accessor) {
+        (Value::List(mut list), Accessor::Index(Value::Number(position))) => {
+            let position = checked_position(*position, list.len());
+            list[position] = value;
+            Value::List(list)
+        }
+        (Value::Map(mut map), Accessor::Index(key)) => {
+            map.insert(key.clone(), value);
+            Value::Map(map)
+        }
+        (Value::Struct(mut instance), Accessor::Field(field)) => {
+            instance.set(field, value);
+            Value::Struct(instance)
+        }
+        (target, Accessor::Index(index)) => {
+            panic!("Cannot assign to index {:?} of {:?}", index, target)
+        }
+        (target, Accessor::Field(field)) => {
+            panic!("Cannot assign to field '{}' of {:?}", field, target)
+        }
+    }
+}
+
+fn checked_position(position: i64, length: usize) -> usize {
+    if position < 0 || position as usize >= length {
+        panic!("Index {} is out of bounds for length {}", position, length);
+    }
+
+    position as usize
+}
+
+/*
+Binds a value to a pattern that has to match, like the ones of declarations, for loops and
+function parameters.
+ */
+fn destructure(pattern: &Pattern, value: Value) -> Vec<(String, Value)> {
+    match_pattern(pattern, value).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/*
+Matches a value against a pattern and returns the name/value pairs it binds, or the reason why
+it didn't match. A list pattern requires a list of exactly the same length, unless it has a rest
+binding which then collects all remaining items.
+ */
+fn match_pattern(pattern: &Pattern, value: Value) -> Result<Vec<(String, Value)>, String> {
+    match pattern {
+        Pattern::Identifier(name) => Ok(vec![(name.clone(), value)]),
+        Pattern::Wildcard => Ok(vec![]),
+        Pattern::Literal(expression) => {
+            let literal = match expression.as_ref() {
+                Expression::Number(number) => Value::Number(*number),
+                Expression::Float(float) => Value::Float(*float),
+                Expression::BigInt(big) => Value::BigInt(big.clone()),
+                Expression::Decimal(decimal) => Value::Decimal(decimal.clone()),
+                Expression::String(string) => Value::String(string.clone()),
+                Expression::Boolean(boolean) => Value::Boolean(*boolean),
+                _ => Value::Null,
+            };
+
+            if values_equal(&literal, &value) {
+                Ok(vec![])
+            } else {
+                Err(format!("{:?} does not match {:?}", value, literal))
+            }
+        }
+        Pattern::List { items, rest } => {
+            let Value::List(mut values) = value else {
+                return Err(format!("Cannot destructure {:?}, expected a list", value));
+            };
+
+            if values.len() < items.len() || (rest.is_none() && values.len() != items.len()) {
+                return Err(format!(
+                    "Cannot destructure a list of {} items into a pattern of {} items",
+                    values.len(),
+                    items.len()
+                ));
+            }
+
+            let remaining = values.split_off(items.len());
+
+            let mut bindings = vec![];
+            for (item, value) in items.iter().zip(values) {
+                bindings.extend(match_pattern(item, value)?);
+            }
+
+            if let Some(rest) = rest {
+                bindings.push((rest.clone(), Value::List(remaining)));
+            }
+
+            Ok(bindings)
+        }
+        Pattern::Variant {
+            identifier,
+            variant,
+            payload,
+        } => {
+            let Value::Variant(instance) = value else {
+                return Err(format!(
+                    "Cannot destructure {:?}, expected {}::{}",
+                    value, identifier, variant
+                ));
+            };
+
+            if instance.identifier != *identifier
+                || instance.variant != *variant
+                || instance.payload.len() != payload.len()
+            {
+                return Err(format!(
+                    "{:?} does not match {}::{} with {} values",
+                    instance,
+                    identifier,
+                    variant,
+                    payload.len()
+                ));
+            }
+
+            let mut bindings = vec![];
+            for (item, value) in payload.iter().zip(instance.payload) {
+                bindings.extend(match_pattern(item, value)?);
+            }
+
+            Ok(bindings)
+        }
+    }
+}
+
+/*
+false, null, zero, NaN and empty strings, lists, maps and ranges are falsy, every other value is
+truthy. Negative numbers are truthy like any other number which is not zero.
+ */
+fn is_truthy_value(value: Value) -> bool {
+    match value {
+        Value::Number(number) => number != 0,
+        Value::Float(float) => float != 0.0 && !float.is_nan(),
+        Value::BigInt(_) => true,
+        Value::Decimal(decimal) => !decimal.is_zero(),
+        Value::Boolean(bool) => bool,
+        Value::String(string) => !string.is_empty(),
+        Value::Null => false,
+        Value::List(list) => !list.is_empty(),
+        Value::Map(map) => !map.is_empty(),
+        Value::Struct(_) => true,
+        Value::Variant(_) => true,
+        Value::Range(range) => !range.is_empty(),
+        Value::Function(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
 
     #[test]
-    fn basic_addition() {
-        assert_eq!(execute_interpreter("5 + 5"), Value::Number(10))
+    fn basic_addition() {
+        assert_eq!(execute_interpreter("5 + 5"), Value::Number(10))
+    }
+
+    #[test]
+    fn three_numbers_addition() {
+        assert_eq!(execute_interpreter("5 + 5 + 5"), Value::Number(15))
+    }
+
+    #[test]
+    fn basic_subtraction() {
+        assert_eq!(execute_interpreter("5 - 5"), Value::Number(0))
+    }
+
+    #[test]
+    fn subtraction_advanced() {
+        // This makes sure we aren't just resolving from righ to left but respecting math rules
+        assert_eq!(execute_interpreter("5 - 5 - 5"), Value::Number(-5))
+    }
+
+    #[test]
+    fn multiplication() {
+        assert_eq!(execute_interpreter("5 * 5"), Value::Number(25))
+    }
+
+    #[test]
+    fn multiplication_advanced() {
+        // This test makes sure we are respecting math rules and aren't just evaluating from left
+        // to right. In this case the equation should be evaluate as 3 + (5 * 5)
+        assert_eq!(execute_interpreter("3 + 5 * 5"), Value::Number(28))
+    }
+
+    #[test]
+    fn division() {
+        assert_eq!(execute_interpreter("10 / 2"), Value::Number(5))
+    }
+
+    #[test]
+    fn division_advanced() {
+        assert_eq!(execute_interpreter("3 + 10 / 5"), Value::Number(5))
+    }
+
+    #[test]
+    fn equation_advanced() {
+        assert_eq!(
+            execute_interpreter("3 + 10 / 5 * 10 - 10 / 2"),
+            Value::Number(18)
+        )
+    }
+
+    #[test]
+    fn white_space() {
+        assert_eq!(execute_interpreter("5     +   5"), Value::Number(10))
+    }
+
+    #[test]
+    fn missing_white_space() {
+        assert_eq!(execute_interpreter("5+5"), Value::Number(10))
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(execute_interpreter("2 * (5 + 5)"), Value::Number(20))
+    }
+
+    #[test]
+    fn multiple_parentheses() {
+        assert_eq!(
+            execute_interpreter("2 * (5 + 5) * (5 + 5)"),
+            Value::Number(200)
+        )
+    }
+
+    #[test]
+    fn nested_parentheses() {
+        assert_eq!(execute_interpreter("2 * (3 + (4 * 5))"), Value::Number(46));
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_open_parentheses() {
+        execute_interpreter("2 + 2 + 5)");
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_closing_parentheses() {
+        execute_interpreter("2 + (2 + 5");
+    }
+
+    #[test]
+    fn unary_expressions() {
+        assert_eq!(execute_interpreter("-5"), Value::Number(-5));
+        assert_eq!(execute_interpreter("--5"), Value::Number(5));
+        assert_eq!(execute_interpreter("-(2 + 3)"), Value::Number(-5));
+        assert_eq!(execute_interpreter("-2 * 3"), Value::Number(-6));
+        assert_eq!(execute_interpreter("2 * -3"), Value::Number(-6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_unary_expression() {
+        execute_interpreter("*5");
+    }
+
+    #[test]
+    fn assignment() {
+        assert_eq!(execute_interpreter("remember x = 5"), Value::Number(5));
+        assert_eq!(execute_interpreter("remember x = 5 + 5"), Value::Number(10));
+        assert_eq!(
+            execute_interpreter("remember x = 5 + 5 + 5"),
+            Value::Number(15)
+        );
+    }
+
+    #[test]
+    fn re_assignment() {
+        assert_eq!(
+            execute_interpreter("remember x = 5; x = 10; x"),
+            Value::Number(10)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn undefined_variable() {
+        execute_interpreter("x + 5");
+    }
+
+    #[test]
+    fn multiple_statements() {
+        assert_eq!(
+            execute_interpreter("remember x = 5; x + 5"),
+            Value::Number(10)
+        )
+    }
+
+    #[test]
+    fn yell() {
+        assert_eq!(execute_interpreter("yell(5 + 5)"), Value::Null);
+        assert_eq!(
+            execute_interpreter("yell(5 + 5); 10 + 10"),
+            Value::Number(20)
+        )
+    }
+    #[test]
+    #[should_panic]
+    fn yell_without_parentheses() {
+        execute_interpreter("yell 5 + 5");
+    }
+
+    #[test]
+    fn block_scoping() {
+        assert_eq!(
+            execute_interpreter("{ 5 + 5; { 10 + 10; }; }"),
+            Value::Number(20)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn variable_does_not_escape_scope() {
+        execute_interpreter("{ remember x = 5; }; x");
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(execute_interpreter("if (true) { 5 }"), Value::Number(5));
+        assert_eq!(execute_interpreter("if (false) { 5 }"), Value::Null);
+        assert_eq!(
+            execute_interpreter("if (false) { 5 } else { 10 }"),
+            Value::Number(10)
+        );
+        assert_eq!(execute_interpreter("if (5 == 5) { 5 }"), Value::Number(5));
+        assert_eq!(
+            execute_interpreter("remember x = 5; if (x + 5 == 10) { 5 }"),
+            Value::Number(5)
+        );
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(execute_interpreter("5 == 5"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5 == 4"), Value::Boolean(false));
+        assert_eq!(execute_interpreter("true == true"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("true == false"), Value::Boolean(false));
+        assert_eq!(execute_interpreter("false == false"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5 == true"), Value::Boolean(false));
+    }
+
+    #[test]
+    fn comparators() {
+        assert_eq!(execute_interpreter("5 > 3"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5 < 3"), Value::Boolean(false));
+        assert_eq!(execute_interpreter("0 > 3"), Value::Boolean(false));
+        assert_eq!(execute_interpreter("0 < 3"), Value::Boolean(true));
+    }
+
+    #[test]
+    #[should_panic]
+    fn comparators_invalid() {
+        execute_interpreter("5 > true");
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            execute_interpreter("\"hello\""),
+            Value::String("hello".to_string())
+        );
+        assert_eq!(
+            execute_interpreter("remember x = \"hello\"; x;"),
+            Value::String("hello".to_string())
+        )
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_string() {
+        execute_interpreter("\"test");
+    }
+
+    #[test]
+    fn while_loop() {
+        assert_eq!(
+            execute_interpreter("remember x = 0; while (x < 5) { x = x + 1 }; x;"),
+            Value::Number(5)
+        )
+    }
+
+    #[test]
+    fn null() {
+        assert_eq!(execute_interpreter("null"), Value::Null)
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(
+            execute_interpreter("[1, 2, 3]"),
+            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(3)])
+        );
+        assert_eq!(
+            execute_interpreter("remember x = [1, 2, 3]; x;"),
+            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(3)])
+        )
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_list_missing_closing_bracket() {
+        execute_interpreter("[1,2");
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_list_missing_comma() {
+        execute_interpreter("[1 2]");
+    }
+
+    #[test]
+    fn for_loop() {
+        assert_eq!(
+            execute_interpreter("for (x in [1]) { yell(x); };"),
+            Value::Null
+        );
+
+        assert_eq!(
+            execute_interpreter("remember x = 0; for (y in [5, 6]) { x = y }; x;"),
+            Value::Number(6)
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined variable 'y'")]
+    fn for_loop_variable_does_not_leak() {
+        execute_interpreter("for (y in [5, 6]) { y }; y;");
+    }
+
+    #[test]
+    fn for_loop_scope() {
+        // The outer x is shadowed, not overwritten
+        assert_eq!(
+            execute_interpreter("remember x = 1; for (x in 0..3) { x = x * 10 }; x"),
+            Value::Number(1)
+        );
+
+        // Each closure keeps the variable of its own iteration, even after the loop has ended
+        assert_eq!(
+            execute_interpreter(
+                "remember closures = [];
+                for (i in [1, 2, 3]) { function get() { i }; closures = closures + [get] };
+                remember results = [];
+                for (closure in closures) { results = results + [closure()] };
+                results"
+            ),
+            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(3)])
+        );
+    }
+
+    #[test]
+    fn leaky_loops() {
+        let options = Options {
+            leaky_loops: true,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            execute_with_options("remember x = 1; for (x in [5, 6]) { x }; x", options),
+            Value::Number(6)
+        );
+        assert_eq!(
+            execute_with_options("for ([key, value] in { \"a\": 1 }) { key }; value", options),
+            Value::Number(1)
+        );
+    }
+
+    #[test]
+    fn destructuring_declaration() {
+        assert_eq!(
+            execute_interpreter("remember [a, b] = [1, 2]; a + b"),
+            Value::Number(3)
+        );
+        assert_eq!(
+            execute_interpreter("remember [a, [b, c]] = [1, [2, 3]]; a + b + c"),
+            Value::Number(6)
+        );
+        assert_eq!(
+            execute_interpreter("remember [a, ...rest] = [1, 2, 3]; rest"),
+            Value::List(vec![Value::Number(2), Value::Number(3)])
+        );
+        assert_eq!(
+            execute_interpreter("remember [a, ...rest] = [1]; rest"),
+            Value::List(vec![])
+        );
+    }
+
+    #[test]
+    fn destructuring_swap() {
+        assert_eq!(
+            execute_interpreter("remember a = 1; remember b = 2; [a, b] = [b, a]; [a, b]"),
+            Value::List(vec![Value::Number(2), Value::Number(1)])
+        );
+    }
+
+    #[test]
+    fn destructuring_for_loop() {
+        assert_eq!(
+            execute_interpreter(
+                "remember x = 0; for ([a, b] in [[1, 2], [3, 4]]) { x = x + a * b }; x"
+            ),
+            Value::Number(14)
+        );
+    }
+
+    #[test]
+    fn destructuring_function_parameters() {
+        assert_eq!(
+            execute_interpreter("function add([a, b]) { a + b }; add([2, 3])"),
+            Value::Number(5)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn destructuring_too_few_items() {
+        execute_interpreter("remember [a, b] = [1]");
+    }
+
+    #[test]
+    #[should_panic]
+    fn destructuring_too_many_items() {
+        execute_interpreter("remember [a, b] = [1, 2, 3]");
+    }
+
+    #[test]
+    #[should_panic]
+    fn destructuring_non_list() {
+        execute_interpreter("remember [a, b] = 5");
+    }
+
+    #[test]
+    fn maps() {
+        let mut map = Map::new();
+        map.insert(Value::String("a".to_string()), Value::Number(1));
+        map.insert(Value::String("b".to_string()), Value::Number(2));
+
+        assert_eq!(
+            execute_interpreter("{ \"a\": 1, \"b\": 1 + 1 }"),
+            Value::Map(map)
+        );
+        assert_eq!(execute_interpreter("{}"), Value::Map(Map::new()));
+    }
+
+    #[test]
+    fn map_indexing() {
+        assert_eq!(
+            execute_interpreter("remember m = { \"a\": 1, \"b\": 2 }; m[\"b\"]"),
+            Value::Number(2)
+        );
+        assert_eq!(
+            execute_interpreter("remember m = { 1: { \"x\": true } }; m[1][\"x\"]"),
+            Value::Boolean(true)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn map_missing_key() {
+        execute_interpreter("remember m = { \"a\": 1 }; m[\"b\"]");
+    }
+
+    #[test]
+    fn map_assignment() {
+        assert_eq!(
+            execute_interpreter("remember m = { \"a\": 1 }; m[\"a\"] = 5; m[\"b\"] = 6; values(m)"),
+            Value::List(vec![Value::Number(5), Value::Number(6)])
+        );
+        assert_eq!(
+            execute_interpreter(
+                "remember m = { \"a\": { \"b\": 1 } }; m[\"a\"][\"b\"] = 2; m[\"a\"][\"b\"]"
+            ),
+            Value::Number(2)
+        );
+    }
+
+    #[test]
+    fn map_iteration() {
+        assert_eq!(
+            execute_interpreter(
+                "remember order = [null, null]; remember i = 0; remember total = 0;
+                for ([key, value] in { \"z\": 1, \"a\": 2 }) {
+                    order[i] = key;
+                    i = i + 1;
+                    total = total + value
+                };
+                [order, total]"
+            ),
+            Value::List(vec![
+                Value::List(vec![
+                    Value::String("z".to_string()),
+                    Value::String("a".to_string())
+                ]),
+                Value::Number(3)
+            ])
+        );
+    }
+
+    #[test]
+    fn map_equality() {
+        assert_eq!(
+            execute_interpreter("{ \"a\": 1, \"b\": 2 } == { \"b\": 2, \"a\": 1 }"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("{ \"a\": 1 } == { \"a\": 2 }"),
+            Value::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn map_builtins() {
+        assert_eq!(
+            execute_interpreter("keys({ \"a\": 1, \"b\": 2 })"),
+            Value::List(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string())
+            ])
+        );
+        assert_eq!(
+            execute_interpreter("has({ \"a\": 1 }, \"a\")"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("has({ \"a\": 1 }, \"b\")"),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "remember m = { \"a\": 1, \"b\": 2 }; m = remove(m, \"a\"); keys(m)"
+            ),
+            Value::List(vec![Value::String("b".to_string())])
+        );
+    }
+
+    #[test]
+    fn list_indexing() {
+        assert_eq!(
+            execute_interpreter("remember x = [1, 2, 3]; x[1] = 5; x[1] + x[2]"),
+            Value::Number(8)
+        );
+        assert_eq!(
+            execute_interpreter("\"hello\"[1]"),
+            Value::String("e".to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn list_index_out_of_bounds() {
+        execute_interpreter("[1, 2][2]");
+    }
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x, y }; remember p = Point { x: 1, y: 2 }; p.x + p.y"
+            ),
+            Value::Number(3)
+        );
+        match execute_interpreter("struct Point { x, y }; Point { y: 2, x: 1 }") {
+            Value::Struct(instance) => {
+                assert_eq!(instance.identifier, "Point");
+                assert_eq!(
+                    instance.fields,
+                    vec![
+                        ("x".to_string(), Value::Number(1)),
+                        ("y".to_string(), Value::Number(2))
+                    ]
+                );
+            }
+            value => panic!("Expected a struct, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn struct_field_assignment() {
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x, y }; remember p = Point { x: 1, y: 2 }; p.x = 5; p.x"
+            ),
+            Value::Number(5)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x, y }; remember points = [Point { x: 1, y: 2 }]; points[0].y = 7; points[0].y"
+            ),
+            Value::Number(7)
+        );
+    }
+
+    #[test]
+    fn struct_methods() {
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x, y };
+                impl Point {
+                    function sum(self) { self.x + self.y };
+                    function scale(self, factor) { Point { x: self.x * factor, y: self.y * factor } }
+                };
+                remember p = Point { x: 1, y: 2 };
+                p.scale(3).sum()"
+            ),
+            Value::Number(9)
+        );
+    }
+
+    #[test]
+    fn struct_equality() {
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x, y }; Point { x: 1, y: 2 } == Point { y: 2, x: 1 }"
+            ),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x, y }; Point { x: 1, y: 2 } == Point { x: 2, y: 2 }"
+            ),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            execute_interpreter("struct A { x }; struct B { x }; A { x: 1 } == B { x: 1 }"),
+            Value::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn struct_printable_representation() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                execute_interpreter("struct Point { x, y }; Point { x: 1, y: 2 }")
+            ),
+            "Struct(Point { x: Number(1), y: Number(2) })"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn struct_missing_field() {
+        execute_interpreter("struct Point { x, y }; Point { x: 1 }");
+    }
+
+    #[test]
+    #[should_panic]
+    fn struct_unknown_field() {
+        execute_interpreter("struct Point { x }; remember p = Point { x: 1 }; p.y = 5");
+    }
+
+    #[test]
+    #[should_panic]
+    fn struct_unknown_method() {
+        execute_interpreter("struct Point { x }; Point { x: 1 }.length()");
+    }
+
+    #[test]
+    fn builtin_methods() {
+        assert_eq!(
+            execute_interpreter(
+                "[[1, 2, 3].len(), \" Hi \".trim().upper(), { \"a\": 1 }.keys(), (1..4).sum()]"
+            ),
+            Value::List(vec![
+                Value::Number(3),
+                Value::String("HI".to_string()),
+                Value::List(vec![Value::String("a".to_string())]),
+                Value::Number(6),
+            ])
+        );
+        assert_eq!(
+            execute_interpreter("\"a,b\".split(\",\").join(\" and \")"),
+            Value::String("a and b".to_string())
+        );
+    }
+
+    #[test]
+    fn functions_as_values() {
+        assert_eq!(
+            execute_interpreter(
+                "function add(a, b) { a + b };
+                remember plus = add;
+                function apply(f, x, y) { f(x, y) };
+                [apply(plus, 1, 2), plus == add, plus == len, reduce([1, 2, 3], add, 10)]"
+            ),
+            Value::List(vec![
+                Value::Number(3),
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Number(16),
+            ])
+        );
+    }
+
+    #[test]
+    fn uniform_function_call_syntax() {
+        assert_eq!(
+            execute_interpreter(
+                "function is_even(x) { x % 2 == 0 };
+                function square(x) { x * x };
+                function add(a, b) { a + b };
+                [[1, 2, 3, 4].filter(is_even).map(square), 5.add(2), [\"a\", \"bc\"].map(len)]"
+            ),
+            Value::List(vec![
+                Value::List(vec![Value::Number(4), Value::Number(16)]),
+                Value::Number(7),
+                Value::List(vec![Value::Number(1), Value::Number(2)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn pipeline() {
+        assert_eq!(
+            execute_interpreter(
+                "function is_even(x) { x % 2 == 0 };
+                function square(x) { x * x };
+                function add(a, b) { a + b };
+                [1..=4 |> filter(is_even) |> map(square) |> sum(), 2 + 3 |> add(b = 1) == 6]"
+            ),
+            Value::List(vec![Value::Number(20), Value::Boolean(true)])
+        );
+    }
+
+    #[test]
+    fn struct_methods_come_before_functions() {
+        assert_eq!(
+            execute_interpreter(
+                "struct Point { x };
+                impl Point { function size(self) { self.x } };
+                function size(point) { 0 };
+                function double(point) { point.x * 2 };
+                remember point = Point { x: 4 };
+                [point.size(), point.double()]"
+            ),
+            Value::List(vec![Value::Number(4), Value::Number(8)])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot call method missing on Number(5)")]
+    fn unknown_method() {
+        execute_interpreter("5.missing()");
+    }
+
+    #[test]
+    fn enums() {
+        assert_eq!(
+            execute_interpreter("enum Status { Pending, Done(result) }; Status::Done(5)"),
+            Value::Variant(Variant {
+                identifier: "Status".to_string(),
+                variant: "Done".to_string(),
+                payload: vec![Value::Number(5)],
+            })
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                execute_interpreter(
+                    "enum Status { Pending, Done(result) }; [Status::Pending, Status::Done(5)]"
+                )
+            ),
+            "List([Variant(Status::Pending), Variant(Status::Done(Number(5)))])"
+        );
+    }
+
+    #[test]
+    fn enum_equality() {
+        assert_eq!(
+            execute_interpreter(
+                "enum Status { Pending, Done(result) }; Status::Pending == Status::Pending"
+            ),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "enum Status { Pending, Done(result) }; Status::Done(1) == Status::Done(2)"
+            ),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "enum Status { Pending, Done(result) }; Status::Done(1) == Status::Pending"
+            ),
+            Value::Boolean(false)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn enum_unknown_variant() {
+        execute_interpreter("enum Status { Pending }; Status::Done");
+    }
+
+    #[test]
+    #[should_panic]
+    fn enum_wrong_payload() {
+        execute_interpreter("enum Status { Pending, Done(result) }; Status::Done(1, 2)");
+    }
+
+    #[test]
+    fn match_variants() {
+        let program = "
+            enum Status { Pending, Done(result), Failed(reason) };
+            function describe(status) {
+                match (status) {
+                    Status::Pending => \"pending\",
+                    Status::Done(result) if (result > 10) => \"big\",
+                    Status::Done(result) => result,
+                    Status::Failed(_) => \"failed\",
+                }
+            };
+            [describe(Status::Pending), describe(Status::Done(50)), describe(Status::Done(3)), describe(Status::Failed(\"oops\"))]
+        ";
+
+        assert_eq!(
+            execute_interpreter(program),
+            Value::List(vec![
+                Value::String("pending".to_string()),
+                Value::String("big".to_string()),
+                Value::Number(3),
+                Value::String("failed".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn match_literals_and_lists() {
+        assert_eq!(
+            execute_interpreter("match (2) { 1 => \"one\", 2 => \"two\", _ => \"many\" }"),
+            Value::String("two".to_string())
+        );
+        assert_eq!(
+            execute_interpreter("match ([1, 2, 3]) { [a] => a, [a, ...rest] => rest }"),
+            Value::List(vec![Value::Number(2), Value::Number(3)])
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn match_without_matching_arm() {
+        execute_interpreter("match (3) { 1 => 1, 2 => 2 }");
+    }
+
+    #[test]
+    #[should_panic]
+    fn match_bindings_do_not_escape() {
+        execute_interpreter("match (3) { x => x }; x");
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            execute_interpreter("0..3"),
+            Value::Range(Range::new(0, 3, 1, false))
+        );
+        assert_eq!(
+            execute_interpreter("list(1..=3)"),
+            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(3)])
+        );
+        assert_eq!(
+            execute_interpreter("list(10..0 step -4)"),
+            Value::List(vec![Value::Number(10), Value::Number(6), Value::Number(2)])
+        );
+        assert_eq!(execute_interpreter("len(0..=10 step 5)"), Value::Number(3));
+        assert_eq!(execute_interpreter("list(5..0)"), Value::List(vec![]));
+    }
+
+    #[test]
+    fn range_for_loop() {
+        assert_eq!(
+            execute_interpreter("remember x = 0; for (i in 1..=4) { x = x + i }; x"),
+            Value::Number(10)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "remember n = 3; remember x = 0; for (i in 0..n * 2 step 2) { x = x + i }; x"
+            ),
+            Value::Number(6)
+        );
+    }
+
+    #[test]
+    fn range_indexing_and_slicing() {
+        assert_eq!(
+            execute_interpreter("(0..100 step 10)[3]"),
+            Value::Number(30)
+        );
+        assert_eq!(
+            execute_interpreter("(0..100 step 10)[1..3]"),
+            Value::Range(Range::new(10, 30, 10, false))
+        );
+        assert_eq!(
+            execute_interpreter("[1, 2, 3, 4][1..=2]"),
+            Value::List(vec![Value::Number(2), Value::Number(3)])
+        );
+        assert_eq!(
+            execute_interpreter("\"hello\"[1..4]"),
+            Value::String("ell".to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn range_index_out_of_bounds() {
+        execute_interpreter("(0..3)[3]");
+    }
+
+    #[test]
+    fn range_membership() {
+        assert_eq!(
+            execute_interpreter("contains(0..10 step 2, 4)"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("contains(0..10 step 2, 5)"),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            execute_interpreter("contains([1, 2], 2)"),
+            Value::Boolean(true)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn range_of_non_numbers() {
+        execute_interpreter("0..\"a\"");
+    }
+
+    #[test]
+    fn modulo() {
+        assert_eq!(execute_interpreter("10 % 4"), Value::Number(2));
+        assert_eq!(execute_interpreter("1 + 10 % 4 * 2"), Value::Number(5));
+    }
+
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(
+            execute_interpreter("remember x = 10; x += 5; x -= 3; x *= 2; x /= 4; x %= 4; x"),
+            Value::Number(2)
+        );
+        assert_eq!(
+            execute_interpreter("remember x = 1; x += 2"),
+            Value::Number(3)
+        );
+    }
+
+    #[test]
+    fn compound_assignment_on_places() {
+        assert_eq!(
+            execute_interpreter("remember x = [1, [2, 3]]; x[1][0] += 10; x"),
+            Value::List(vec![
+                Value::Number(1),
+                Value::List(vec![Value::Number(12), Value::Number(3)])
+            ])
+        );
+        assert_eq!(
+            execute_interpreter("remember m = { \"count\": 1 }; m[\"count\"] *= 5; m[\"count\"]"),
+            Value::Number(5)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "struct Counter { value }; remember c = Counter { value: 1 }; c.value += 1; c.value"
+            ),
+            Value::Number(2)
+        );
+    }
+
+    #[test]
+    fn compound_assignment_evaluates_place_once() {
+        assert_eq!(
+            execute_interpreter(
+                "remember calls = 0;
+                remember x = [10, 20];
+                x[{ calls += 1; 0 }] += 1;
+                [x, calls]"
+            ),
+            Value::List(vec![
+                Value::List(vec![Value::Number(11), Value::Number(20)]),
+                Value::Number(1)
+            ])
+        );
+    }
+
+    #[test]
+    fn compound_assignment_keeps_writes_of_the_right_side() {
+        assert_eq!(
+            execute_interpreter(
+                "remember xs = [0, 0];
+                function g() { xs[1] = 5; 1 };
+                xs[0] += g();
+                xs"
+            ),
+            Value::List(vec![Value::Number(1), Value::Number(5)])
+        );
+        assert_eq!(
+            execute_interpreter("remember x = 1; function g() { x = 10; 1 }; x += g(); x"),
+            Value::Number(11)
+        );
+    }
+
+    #[test]
+    fn increment_and_decrement() {
+        assert_eq!(
+            execute_interpreter("remember x = 0; x++; x++; x--; x"),
+            Value::Number(1)
+        );
+        assert_eq!(
+            execute_interpreter("remember x = [5]; x[0]++; x[0]"),
+            Value::Number(6)
+        );
+        assert_eq!(execute_interpreter("--5"), Value::Number(5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn compound_assignment_to_undefined_variable() {
+        execute_interpreter("x += 1");
+    }
+
+    #[test]
+    fn function_calls_in_expressions() {
+        assert_eq!(
+            execute_interpreter(
+                "function add(a, b) { a + b }; remember x = add(1, 2) * add(3, 4); x"
+            ),
+            Value::Number(21)
+        );
+        assert_eq!(
+            execute_interpreter("function double(a) { a * 2 }; [double(1), double(double(2))][1]"),
+            Value::Number(8)
+        );
+    }
+
+    #[test]
+    fn default_parameters() {
+        assert_eq!(
+            execute_interpreter(
+                "function greet(name, greeting = \"hi\") { [greeting, name] }; greet(\"a\")"
+            ),
+            Value::List(vec![
+                Value::String("hi".to_string()),
+                Value::String("a".to_string())
+            ])
+        );
+        assert_eq!(
+            execute_interpreter(
+                "function greet(name, greeting = \"hi\") { [greeting, name] }; greet(\"a\", \"yo\")"
+            ),
+            Value::List(vec![
+                Value::String("yo".to_string()),
+                Value::String("a".to_string())
+            ])
+        );
+        assert_eq!(
+            execute_interpreter("function area(width, height = width) { width * height }; area(3)"),
+            Value::Number(9)
+        );
+    }
+
+    #[test]
+    fn named_arguments() {
+        assert_eq!(
+            execute_interpreter("function sub(a, b = 1) { a - b }; sub(b = 10, a = 3)"),
+            Value::Number(-7)
+        );
+        assert_eq!(
+            execute_interpreter("function f(a, b = 2, c = 3) { [a, b, c] }; f(1, c = 30)"),
+            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(30)])
+        );
+    }
+
+    #[test]
+    fn rest_parameters_and_spread() {
+        assert_eq!(
+            execute_interpreter("function f(first, ...rest) { [first, rest] }; f(1, 2, 3)"),
+            Value::List(vec![
+                Value::Number(1),
+                Value::List(vec![Value::Number(2), Value::Number(3)])
+            ])
+        );
+        assert_eq!(
+            execute_interpreter("function f(...rest) { rest }; f()"),
+            Value::List(vec![])
+        );
+        assert_eq!(
+            execute_interpreter(
+                "function add(a, b, c) { a + b + c }; remember xs = [2, 3]; add(1, ...xs)"
+            ),
+            Value::Number(6)
+        );
+        assert_eq!(
+            execute_interpreter("function add(a, b) { a + b }; add(...1..3)"),
+            Value::Number(3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Function f takes 1 to 2 arguments but got 3")]
+    fn too_many_arguments() {
+        execute_interpreter("function f(a, b = 1) { a }; f(1, 2, 3)");
+    }
+
+    #[test]
+    #[should_panic(expected = "Function f is missing the argument for parameter 'b'")]
+    fn missing_argument() {
+        execute_interpreter("function f(a, b) { a }; f(1)");
+    }
+
+    #[test]
+    #[should_panic(expected = "Function f does not have a parameter named 'c'")]
+    fn unknown_named_argument() {
+        execute_interpreter("function f(a) { a }; f(c = 1)");
+    }
+
+    #[test]
+    #[should_panic(expected = "Parameter 'a' of function f was given more than once")]
+    fn duplicate_argument() {
+        execute_interpreter("function f(a) { a }; f(1, a = 1)");
+    }
+
+    #[test]
+    #[should_panic]
+    fn positional_after_named_argument() {
+        execute_interpreter("function f(a, b) { a }; f(a = 1, 2)");
+    }
+
+    #[test]
+    fn declaration_shadows_outer_variable() {
+        assert_eq!(
+            execute_interpreter("remember x = 1; { remember x = 2; x = 3; }; x"),
+            Value::Number(1)
+        );
+        assert_eq!(
+            execute_interpreter("remember x = 1; { remember x = 2; x }"),
+            Value::Number(2)
+        );
+        assert_eq!(
+            execute_interpreter("remember x = 1; { x = 2; }; x"),
+            Value::Number(2)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot assign to undeclared variable 'x'")]
+    fn assignment_to_undeclared_variable() {
+        execute_interpreter("x = 5");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot assign to undeclared variable 'b'")]
+    fn destructuring_assignment_to_undeclared_variable() {
+        execute_interpreter("remember a = 1; [a, b] = [1, 2]");
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(execute_interpreter("fix x = 1; x + 1"), Value::Number(2));
+        assert_eq!(
+            execute_interpreter("fix x = 1; { remember x = 2; x = 3; x }"),
+            Value::Number(3)
+        );
+        assert_eq!(
+            execute_interpreter("fix [a, b] = [1, 2]; a + b"),
+            Value::Number(3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot assign to constant 'x'")]
+    fn constant_reassignment() {
+        execute_interpreter("fix x = 1; x = 2");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot assign to constant 'x'")]
+    fn constant_reassignment_from_inner_scope() {
+        execute_interpreter("fix x = 1; { x += 1 }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot assign to constant 'xs'")]
+    fn constant_index_assignment() {
+        execute_interpreter("fix xs = [1, 2]; xs[0] = 5");
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined variable 'secret'")]
+    fn functions_do_not_see_caller_variables() {
+        execute_interpreter(
+            "function show() { secret };
+            function caller() { remember secret = 1; show() };
+            caller()",
+        );
+    }
+
+    #[test]
+    fn functions_see_variables_of_their_definition() {
+        assert_eq!(
+            execute_interpreter(
+                "remember x = \"global\";
+                function show() { x };
+                function caller() { remember x = \"local\"; show() };
+                caller()"
+            ),
+            Value::String("global".to_string())
+        );
+    }
+
+    #[test]
+    fn nested_functions_capture_enclosing_scope() {
+        assert_eq!(
+            execute_interpreter(
+                "function counter(start) {
+                    remember count = start;
+                    function next() { count += 1; count };
+                    next();
+                    next()
+                };
+                counter(10)"
+            ),
+            Value::Number(12)
+        );
+    }
+
+    #[test]
+    fn recursion() {
+        assert_eq!(
+            execute_interpreter(
+                "function factorial(n) { if (n < 2) { 1 } else { n * factorial(n - 1) } }; factorial(5)"
+            ),
+            Value::Number(120)
+        );
+    }
+
+    #[test]
+    fn function_hoisting() {
+        assert_eq!(
+            execute_interpreter("remember x = double(2); function double(a) { a * 2 }; x"),
+            Value::Number(4)
+        );
+        assert_eq!(
+            execute_interpreter("{ remember x = double(2); function double(a) { a * 2 }; x }"),
+            Value::Number(4)
+        );
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        assert_eq!(
+            execute_interpreter(
+                "function is_even(n) { if (n == 0) { true } else { is_odd(n - 1) } };
+                function is_odd(n) { if (n == 0) { false } else { is_even(n - 1) } };
+                [is_even(10), is_odd(7), is_even(3)]"
+            ),
+            Value::List(vec![
+                Value::Boolean(true),
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Call to undefined function 'missing'")]
+    fn undefined_function_is_reported_before_running() {
+        execute_interpreter("yell(1); function a() { missing() }; 5");
+    }
+
+    #[test]
+    fn float() {
+        assert_eq!(execute_interpreter("5.5"), Value::Float(5.5))
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            execute_interpreter("function test() { 5 }; test()"),
+            Value::Number(5)
+        );
+    }
+
+    #[test]
+    fn functions_with_params() {
+        assert_eq!(
+            execute_interpreter("function test(x) { x + 5 }; test(2)"),
+            Value::Number(7)
+        );
+        assert_eq!(
+            execute_interpreter("function add(a, b) { a + b }; add(5, 5)"),
+            Value::Number(10)
+        )
+    }
+
+    #[test]
+    fn functions_variable_scope() {
+        assert_eq!(
+            execute_interpreter("remember x = 5; function test(x) { x  + 5 }; test(2)"),
+            Value::Number(7)
+        );
+        assert_eq!(
+            execute_interpreter("remember y = 5; function test(x) { x  + y }; test(2)"),
+            Value::Number(7)
+        )
+    }
+
+    #[test]
+    fn modules() {
+        assert_eq!(
+            execute_file("./src/examples/modules.nali", Options::default()),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "import { square, unit } from \"src/examples/lib/math.nali\"; square(4) + unit"
+            ),
+            Value::Number(17)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "does not export 'circle'")]
+    fn import_of_missing_export() {
+        execute_interpreter("import { circle } from \"src/examples/lib/geometry.nali\"");
+    }
+
+    #[test]
+    #[should_panic(expected = "Call to undefined function 'unit'")]
+    fn only_imported_names_are_visible() {
+        execute_interpreter("import { square } from \"src/examples/lib/math.nali\"; unit()");
+    }
+
+    #[test]
+    fn modules_are_evaluated_once() {
+        let mut program = parser::parse(&tokenizer::tokenize(
+            "import \"src/examples/lib/geometry.nali\"; import \"src/examples/lib/math.nali\";",
+        ));
+        let env = Environment::new(None);
+
+        let mut interpreter = Interpreter::new(Options::default());
+        interpreter.run_program(&mut program, &env);
+
+        let closure = |name: &str, env: &Rc<RefCell<Environment>>| match env.borrow().get(name) {
+            Some(EnvironmentRecord::Function(function)) => function.closure.env(),
+            _ => panic!("{} is not a function", name),
+        };
+
+        // geometry imports square from math as well, which must be the very same function
+        let geometry = closure("make_square", &env);
+        assert!(Rc::ptr_eq(
+            &closure("square", &env),
+            &closure("square", &geometry)
+        ));
+        assert_eq!(interpreter.modules.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Circular import")]
+    fn circular_import() {
+        execute_file("./src/examples/lib/cycle_a.nali", Options::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "import is only allowed at the top level")]
+    fn nested_import() {
+        execute_interpreter("if (true) { import \"src/examples/lib/math.nali\" }");
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        assert_eq!(execute_interpreter("5.5 + 1"), Value::Float(6.5));
+        assert_eq!(execute_interpreter("1 - 0.5 * 3"), Value::Float(-0.5));
+        assert_eq!(execute_interpreter("7 / 2"), Value::Number(3));
+        assert_eq!(execute_interpreter("7 / 2.0"), Value::Float(3.5));
+        assert_eq!(execute_interpreter("5.5 % 2"), Value::Float(1.5));
+        assert_eq!(execute_interpreter("-(2.5)"), Value::Float(-2.5));
+        assert_eq!(
+            execute_interpreter("remember x = 1; x += 0.5; x"),
+            Value::Float(1.5)
+        );
+        assert_eq!(execute_interpreter("sum([1, 2.5])"), Value::Float(3.5));
+    }
+
+    #[test]
+    fn mixed_comparisons() {
+        assert_eq!(execute_interpreter("1 > 0.5"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("0.5 < 1"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5 == 5.0"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5.0 == 5"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5 == 5.5"), Value::Boolean(false));
+        assert_eq!(
+            execute_interpreter("[1, 2] == [1.0, 2]"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("match (2.0) { 2 => \"two\", _ => \"other\" }"),
+            Value::String("two".to_string())
+        );
+    }
+
+    #[test]
+    fn numeric_equality_inside_values() {
+        assert_eq!(
+            execute_interpreter("{ 1: \"a\" } == { 1.0: \"a\" }"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("remember m = { 1: \"a\" }; m[1.0] = \"b\"; [len(m), m[1]]"),
+            Value::List(vec![Value::Number(1), Value::String("b".to_string())])
+        );
+        assert_eq!(
+            execute_interpreter("struct P { x }; P { x: 1 } == P { x: 1.0 }"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("enum E { A(x) }; E::A(2) == E::A(2.0d)"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("[contains([1, 2], 2.0), [1, 2].contains(2.0), 2.0 in [1, 2]]"),
+            Value::List(vec![Value::Boolean(true); 3])
+        );
+    }
+
+    #[test]
+    fn nan_and_infinity() {
+        assert_eq!(execute_interpreter("1.0 / 0"), Value::Float(f64::INFINITY));
+        assert_eq!(
+            execute_interpreter("-1 / 0.0"),
+            Value::Float(f64::NEG_INFINITY)
+        );
+        assert_eq!(
+            execute_interpreter("is_nan(0.0 / 0.0)"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("remember nan = float(\"NaN\"); nan == nan"),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            execute_interpreter("remember nan = float(\"NaN\"); [nan > 0, nan < 0]"),
+            Value::List(vec![Value::Boolean(false), Value::Boolean(false)])
+        );
+        assert_eq!(
+            execute_interpreter("float(\"inf\") > 1000000"),
+            Value::Boolean(true)
+        );
+    }
+
+    #[test]
+    fn number_conversions() {
+        assert_eq!(execute_interpreter("int(-2.7)"), Value::Number(-2));
+        assert_eq!(execute_interpreter("\"42\".int()"), Value::Number(42));
+        assert_eq!(execute_interpreter("3.float()"), Value::Float(3.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot convert inf to an integer")]
+    fn infinity_to_integer() {
+        execute_interpreter("int(1.0 / 0)");
+    }
+
+    #[test]
+    fn string_and_list_operators() {
+        assert_eq!(
+            execute_interpreter("\"ab\" + \"cd\""),
+            Value::String("abcd".to_string())
+        );
+        assert_eq!(
+            execute_interpreter("\"ab\" * 3"),
+            Value::String("ababab".to_string())
+        );
+        assert_eq!(
+            execute_interpreter("2 * [1, 2] + [3]"),
+            execute_interpreter("[1, 2, 1, 2, 3]")
+        );
+        assert_eq!(
+            execute_interpreter("remember xs = [1]; xs += [2]; xs"),
+            execute_interpreter("[1, 2]")
+        );
+        assert_eq!(execute_interpreter("[1] * 0"), Value::List(vec![]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot repeat something -1 times")]
+    fn negative_repetition() {
+        execute_interpreter("\"ab\" * -1");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot apply Add to String(\"a\") and Number(1)")]
+    fn adding_string_and_number() {
+        execute_interpreter("\"a\" + 1");
+    }
+
+    #[test]
+    fn lexicographic_ordering() {
+        assert_eq!(
+            execute_interpreter("\"apple\" < \"banana\""),
+            Value::Boolean(true)
+        );
+        assert_eq!(execute_interpreter("\"b\" > \"abc\""), Value::Boolean(true));
+        assert_eq!(execute_interpreter("[1, 2] < [1, 3]"), Value::Boolean(true));
+        assert_eq!(
+            execute_interpreter("[1, 2] < [1, 2, 0]"),
+            Value::Boolean(true)
+        );
+        assert_eq!(execute_interpreter("[2] > [1, 5]"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("[1.5] < [2]"), Value::Boolean(true));
+    }
+
+    #[test]
+    fn membership() {
+        assert_eq!(execute_interpreter("2 in [1, 2, 3]"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("2.0 in [1, 2]"), Value::Boolean(true));
+        assert_eq!(
+            execute_interpreter("\"ab\" in \"cabd\""),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("\"x\" in \"cabd\""),
+            Value::Boolean(false)
+        );
+        assert_eq!(execute_interpreter("5 in 0..5"), Value::Boolean(false));
+        assert_eq!(
+            execute_interpreter("\"a\" in { \"a\": 1 }"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter(
+                "remember found = 0; for (x in [1, 4]) { if (x in 3..5) { found = x } }; found"
+            ),
+            Value::Number(4)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero in '10 / (x - 5)' at 1:17")]
+    fn division_by_zero() {
+        execute_interpreter("remember x = 5; 10 / (x - 5)");
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero in 'y / x' at 4:3")]
+    fn runtime_error_points_at_the_statement_running() {
+        execute_interpreter(
+            "
+function f(x) {
+  remember y = 1;
+  y / x
+};
+f(0) + 1",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero in 'f() / 0' at 5:1")]
+    fn runtime_error_after_a_call_points_at_the_caller() {
+        execute_interpreter(
+            "
+function f() {
+  1
+};
+f() / 0",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by zero in 'x %= 0' at 1:17")]
+    fn remainder_by_zero() {
+        execute_interpreter("remember x = 5; x %= 0");
+    }
+
+    #[test]
+    fn big_integers() {
+        assert_eq!(
+            execute_interpreter("remember big = 9223372036854775807; big * 2 + 3"),
+            execute_interpreter("18446744073709551617")
+        );
+        assert_eq!(
+            execute_interpreter("remember x = -9223372036854775807 - 1; -x"),
+            execute_interpreter("9223372036854775808")
+        );
+        assert_eq!(
+            execute_interpreter("sum([9223372036854775807, 1])"),
+            execute_interpreter("9223372036854775808")
+        );
+        assert_eq!(
+            execute_interpreter("100000000000000000000 * 100000000000000000000 / 3"),
+            execute_interpreter("3333333333333333333333333333333333333333")
+        );
+        assert_eq!(
+            execute_interpreter("int(\"-100000000000000000000\") % 7"),
+            Value::Number(-2)
+        );
+    }
+
+    #[test]
+    fn big_integers_become_numbers_again() {
+        assert_eq!(
+            execute_interpreter("remember big = 9223372036854775807 + 1; big - 1"),
+            Value::Number(i64::MAX)
+        );
+        assert_eq!(
+            execute_interpreter("-9223372036854775808"),
+            Value::Number(i64::MIN)
+        );
+        assert_eq!(
+            execute_interpreter("100000000000000000000 / 100000000000000000000"),
+            Value::Number(1)
+        );
     }
 
     #[test]
-    fn three_numbers_addition() {
-        assert_eq!(execute_interpreter("5 + 5 + 5"), Value::Number(15))
+    fn big_integer_comparisons() {
+        assert_eq!(
+            execute_interpreter("100000000000000000000 > 5"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("-100000000000000000000 < -5"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("100000000000000000000 == 100000000000000000000.0"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("100000000000000000000 + 0.5"),
+            Value::Float(1e20)
+        );
     }
 
     #[test]
-    fn basic_subtraction() {
-        assert_eq!(execute_interpreter("5 - 5"), Value::Number(0))
+    #[should_panic(expected = "Division by zero in 'big / 0' at 1:39")]
+    fn big_integer_division_by_zero() {
+        execute_interpreter("remember big = 100000000000000000000; big / 0");
     }
 
     #[test]
-    fn subtraction_advanced() {
-        // This makes sure we aren't just resolving from righ to left but respecting math rules
-        assert_eq!(execute_interpreter("5 - 5 - 5"), Value::Number(-5))
+    fn wrapping_and_saturating_arithmetic() {
+        assert_eq!(
+            execute_interpreter("wrapping_add(9223372036854775807, 1)"),
+            Value::Number(i64::MIN)
+        );
+        assert_eq!(
+            execute_interpreter("9223372036854775807.saturating_mul(2)"),
+            Value::Number(i64::MAX)
+        );
+        assert_eq!(
+            execute_interpreter("saturating_sub(-9223372036854775807, 5)"),
+            Value::Number(i64::MIN)
+        );
+        assert_eq!(execute_interpreter("wrapping_mul(3, 4)"), Value::Number(12));
+        assert_eq!(
+            execute_interpreter("saturating_add(-9223372036854775808, -1)"),
+            Value::Number(i64::MIN)
+        );
     }
 
     #[test]
-    fn multiplication() {
-        assert_eq!(execute_interpreter("5 * 5"), Value::Number(25))
+    #[should_panic(
+        expected = "wrapping_add expects 64-bit integers, 9223372036854775808 is too large"
+    )]
+    fn wrapping_arithmetic_on_big_integers() {
+        execute_interpreter("wrapping_add(9223372036854775808, 1)");
     }
 
     #[test]
-    fn multiplication_advanced() {
-        // This test makes sure we are respecting math rules and aren't just evaluating from left
-        // to right. In this case the equation should be evaluate as 3 + (5 * 5)
-        assert_eq!(execute_interpreter("3 + 5 * 5"), Value::Number(28))
+    fn decimals() {
+        let decimal = |text: &str| Value::Decimal(Decimal::parse(text).unwrap());
+
+        assert_eq!(
+            execute_interpreter("0.1d + 0.2d == 0.3d"),
+            Value::Boolean(true)
+        );
+        assert_eq!(execute_interpreter("19.99d * 3"), decimal("59.97"));
+        assert_eq!(execute_interpreter("10.00d - 0.01d"), decimal("9.99"));
+        assert_eq!(execute_interpreter("10.00d / 4"), decimal("2.50"));
+        assert_eq!(execute_interpreter("7.5d % 2"), decimal("1.5"));
+        assert_eq!(execute_interpreter("-(1.25d)"), decimal("-1.25"));
+        assert_eq!(
+            execute_interpreter(
+                "remember total = 0d; for (price in [1.10d, 2.20d]) { total += price }; total"
+            ),
+            decimal("3.30")
+        );
     }
 
     #[test]
-    fn division() {
-        assert_eq!(execute_interpreter("10 / 2"), Value::Number(5))
+    fn decimal_comparisons() {
+        assert_eq!(execute_interpreter("12.50d == 12.5d"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("5 == 5.00d"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("0.99d < 1"), Value::Boolean(true));
+        assert_eq!(
+            execute_interpreter("100000000000000000000.5d > 100000000000000000000"),
+            Value::Boolean(true)
+        );
+        assert_eq!(execute_interpreter("1.5d > 1.25"), Value::Boolean(true));
     }
 
     #[test]
-    fn division_advanced() {
-        assert_eq!(execute_interpreter("3 + 10 / 5"), Value::Number(5))
+    fn decimal_rounding_and_division() {
+        let decimal = |text: &str| Value::Decimal(Decimal::parse(text).unwrap());
+
+        assert_eq!(
+            execute_interpreter("round(2.675d, 2, \"half_up\")"),
+            decimal("2.68")
+        );
+        assert_eq!(execute_interpreter("2.665d.round(2)"), decimal("2.66"));
+        assert_eq!(execute_interpreter("divide(10, 3, 2)"), decimal("3.33"));
+        assert_eq!(
+            execute_interpreter("10d.divide(3, 2, \"up\")"),
+            decimal("3.34")
+        );
+        assert_eq!(
+            execute_interpreter("decimal(0.1) + decimal(\"0.20\")"),
+            decimal("0.30")
+        );
+        assert_eq!(execute_interpreter("int(-2.99d)"), Value::Number(-2));
     }
 
     #[test]
-    fn equation_advanced() {
-        assert_eq!(
-            execute_interpreter("3 + 10 / 5 * 10 - 10 / 2"),
-            Value::Number(18)
-        )
+    #[should_panic(expected = "convert one of them with decimal or float first")]
+    fn decimals_and_floats_do_not_mix() {
+        execute_interpreter("1.5d + 0.5");
     }
 
     #[test]
-    fn white_space() {
-        assert_eq!(execute_interpreter("5     +   5"), Value::Number(10))
+    #[should_panic(expected = "Unknown rounding mode 'nearest'")]
+    fn unknown_rounding_mode() {
+        execute_interpreter("round(1.5d, 0, \"nearest\")");
     }
 
     #[test]
-    fn missing_white_space() {
-        assert_eq!(execute_interpreter("5+5"), Value::Number(10))
+    #[should_panic(expected = "Division by zero in '1.5d / 0' at 1:1")]
+    fn decimal_division_by_zero() {
+        execute_interpreter("1.5d / 0");
     }
 
     #[test]
-    fn parentheses() {
-        assert_eq!(execute_interpreter("2 * (5 + 5)"), Value::Number(20))
+    fn truthiness() {
+        let falsy = [
+            "false",
+            "null",
+            "0",
+            "0.0",
+            "float(\"NaN\")",
+            "0.00d",
+            "\"\"",
+            "[]",
+            "{}",
+            "0..0",
+        ];
+        let truthy = [
+            "true",
+            "-1",
+            "0.5",
+            "-0.01d",
+            "-123456789012345678901234567890",
+            "\"false\"",
+            "[0]",
+            "{ \"a\": null }",
+            "0..1",
+        ];
+
+        for value in falsy {
+            let code = format!("if ({}) {{ 1 }} else {{ 2 }}", value);
+            assert_eq!(execute_interpreter(&code), Value::Number(2), "{}", value);
+        }
+        for value in truthy {
+            let code = format!("if ({}) {{ 1 }} else {{ 2 }}", value);
+            assert_eq!(execute_interpreter(&code), Value::Number(1), "{}", value);
+        }
     }
 
     #[test]
-    fn multiple_parentheses() {
+    fn truthiness_in_while_and_match_guards() {
         assert_eq!(
-            execute_interpreter("2 * (5 + 5) * (5 + 5)"),
-            Value::Number(200)
-        )
+            execute_interpreter("remember x = -3; while (x) { x = x + 1 }; x"),
+            Value::Number(0)
+        );
+        assert_eq!(
+            execute_interpreter("match ([]) { items if (items) => 1, _ => 2 }"),
+            Value::Number(2)
+        );
     }
 
     #[test]
-    fn nested_parentheses() {
-        assert_eq!(execute_interpreter("2 * (3 + (4 * 5))"), Value::Number(46));
+    fn logical_operators() {
+        assert_eq!(execute_interpreter("true && 1"), Value::Boolean(true));
+        assert_eq!(execute_interpreter("[] || \"\""), Value::Boolean(false));
+        assert_eq!(execute_interpreter("!0"), Value::Boolean(true));
+        assert_eq!(
+            execute_interpreter("!false && 2 > 1 || false"),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            execute_interpreter("!(1 > 2 || 3 > 4)"),
+            Value::Boolean(true)
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn missing_open_parentheses() {
-        execute_interpreter("2 + 2 + 5)");
+    fn logical_operators_short_circuit() {
+        assert_eq!(
+            execute_interpreter(
+                "remember calls = 0; function f() { calls = calls + 1; true }; false && f(); true || f(); calls"
+            ),
+            Value::Number(0)
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn missing_closing_parentheses() {
-        execute_interpreter("2 + (2 + 5");
+    fn strict_mode_accepts_booleans() {
+        let strict = Options {
+            strict: true,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            execute_with_options(
+                "remember x = 3; while (x > 0) { x = x - 1 }; !(x == 0) || x < 1",
+                strict
+            ),
+            Value::Boolean(true)
+        );
     }
 
     #[test]
-    fn unary_expressions() {
-        assert_eq!(execute_interpreter("-5"), Value::Number(-5));
-        assert_eq!(execute_interpreter("--5"), Value::Number(5));
-        assert_eq!(execute_interpreter("-(2 + 3)"), Value::Number(-5));
-        assert_eq!(execute_interpreter("-2 * 3"), Value::Number(-6));
-        assert_eq!(execute_interpreter("2 * -3"), Value::Number(-6));
+    #[should_panic(
+        expected = "Type error: if expects a boolean in strict mode, got Number(1) from 'x'"
+    )]
+    fn strict_mode_rejects_non_boolean_if() {
+        execute_with_options(
+            "remember x = 1; if (x) { 1 }",
+            Options {
+                strict: true,
+                ..Options::default()
+            },
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn invalid_unary_expression() {
-        execute_interpreter("*5");
+    #[should_panic(expected = "Type error: && expects a boolean in strict mode, got List([])")]
+    fn strict_mode_rejects_non_boolean_operands() {
+        execute_with_options(
+            "true && []",
+            Options {
+                strict: true,
+                ..Options::default()
+            },
+        );
     }
 
     #[test]
-    fn assignment() {
-        assert_eq!(execute_interpreter("remember x = 5"), Value::Number(5));
-        assert_eq!(execute_interpreter("remember x = 5 + 5"), Value::Number(10));
+    fn resolved_variables() {
         assert_eq!(
-            execute_interpreter("remember x = 5 + 5 + 5"),
-            Value::Number(15)
+            execute_interpreter(
+                "remember x = 1;
+                function f(y) { { x + y } };
+                { remember x = 10; yell(x); f(x) }"
+            ),
+            Value::Number(11)
+        );
+        assert_eq!(
+            execute_interpreter("remember x = 1; { remember y = x; remember x = 2; [y, x] }"),
+            Value::List(vec![Value::Number(1), Value::Number(2)])
         );
     }
 
     #[test]
-    fn re_assignment() {
+    #[should_panic(expected = "Undefined variable 'missing'")]
+    fn undefined_variables_are_reported_before_running() {
+        execute_interpreter("if (false) { missing }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Variable 'x' is used before its declaration")]
+    fn closure_called_before_declaration() {
+        execute_interpreter("function f() { x }; f(); remember x = 1");
+    }
+
+    #[test]
+    fn call_chain_collapses_the_repeating_cycle() {
+        let calls = |names: &str| -> Vec<String> { names.split(' ').map(String::from).collect() };
+
+        assert_eq!(call_chain(&calls("f f f")), "f repeated 3 times");
         assert_eq!(
-            execute_interpreter("remember x = 5; x = 10; x"),
-            Value::Number(10)
+            call_chain(&calls("outer even odd even odd even")),
+            "outer -> even -> (odd -> even) repeated 2 times"
         );
+        assert_eq!(call_chain(&calls("a b c")), "a -> b -> c");
     }
 
     #[test]
-    #[should_panic]
-    fn undefined_variable() {
-        execute_interpreter("x + 5");
+    #[should_panic(
+        expected = "Maximum recursion depth exceeded after 5 nested calls: start -> (ping -> pong) repeated 2 times"
+    )]
+    fn maximum_call_depth() {
+        execute_with_options(
+            "function ping() { pong() }; function pong() { ping() }; function start() { ping() }; start()",
+            Options {
+                max_call_depth: 5,
+                ..Options::default()
+            },
+        );
     }
 
     #[test]
-    fn multiple_statements() {
+    fn calls_return_below_the_maximum_depth() {
+        let options = Options {
+            max_call_depth: 3,
+            ..Options::default()
+        };
+
         assert_eq!(
-            execute_interpreter("remember x = 5; x + 5"),
-            Value::Number(10)
-        )
+            execute_with_options(
+                "function f(n) { if (n > 0) { f(n - 1) } else { 0 } }; [f(2), f(2), [1] |> map(f)]",
+                options
+            ),
+            Value::List(vec![
+                Value::Number(0),
+                Value::Number(0),
+                Value::List(vec![Value::Number(0)])
+            ])
+        );
     }
 
     #[test]
-    fn yell() {
-        assert_eq!(execute_interpreter("yell(5 + 5)"), Value::Null);
-        assert_eq!(
-            execute_interpreter("yell(5 + 5); 10 + 10"),
-            Value::Number(20)
-        )
+    #[should_panic(
+        expected = "Maximum recursion depth exceeded after 1000 nested calls: forever repeated 1000 times"
+    )]
+    fn unbounded_recursion_on_a_dedicated_stack() {
+        let options = Options::default();
+
+        run_on_stack(options.stack_size(), || {
+            execute_with_options(
+                "function forever(n) { forever(n + 1) }; forever(0)",
+                options,
+            );
+        });
     }
+
     #[test]
-    #[should_panic]
-    fn yell_without_parentheses() {
-        execute_interpreter("yell 5 + 5");
+    #[should_panic(expected = "Maximum stack size exceeded after")]
+    fn deep_nesting_within_calls_on_a_dedicated_stack() {
+        let options = Options::default();
+        // Far fewer calls than allowed, but every one of them nests a thousand blocks
+        let body = format!("{}f(n - 1){}", "{ ".repeat(1000), " }".repeat(1000));
+        let code = format!(
+            "function f(n) {{ if (n > 0) {} else {{ 0 }} }}; f(900)",
+            body
+        );
+
+        run_on_stack(options.stack_size(), || {
+            execute_with_options(&code, options);
+        });
     }
 
     #[test]
-    fn block_scoping() {
-        assert_eq!(
-            execute_interpreter("{ 5 + 5; { 10 + 10; }; }"),
-            Value::Number(20)
+    #[should_panic(expected = "Enum Status does not have a variant 'Finished'")]
+    fn match_pattern_with_unknown_variant() {
+        execute_interpreter(
+            "enum Status { Pending, Done(result) };
+            match (Status::Pending) { Status::Pending => 1, Status::Finished(x) => 2 }",
         );
     }
 
     #[test]
-    #[should_panic]
-    fn variable_does_not_escape_scope() {
-        execute_interpreter("{ remember x = 5; }; x");
+    #[should_panic(expected = "Variant Status::Done expects 1 values but got 2")]
+    fn match_pattern_with_wrong_payload() {
+        execute_interpreter(
+            "enum Status { Pending, Done(result) };
+            match ([Status::Pending]) { [Status::Done(a, b)] => 1, _ => 2 }",
+        );
     }
 
     #[test]
-    fn conditions() {
-        assert_eq!(execute_interpreter("if (true) { 5 }"), Value::Number(5));
-        assert_eq!(execute_interpreter("if (false) { 5 }"), Value::Null);
+    fn ranges_at_the_limits_of_i64() {
         assert_eq!(
-            execute_interpreter("if (false) { 5 } else { 10 }"),
-            Value::Number(10)
+            execute_interpreter("len(0..=9223372036854775807)"),
+            Value::BigInt(BigInt::parse("9223372036854775808").unwrap())
         );
-        assert_eq!(execute_interpreter("if (5 == 5) { 5 }"), Value::Number(5));
         assert_eq!(
-            execute_interpreter("remember x = 5; if (x + 5 == 10) { 5 }"),
-            Value::Number(5)
+            execute_interpreter("9223372036854775806..=9223372036854775807 |> list()"),
+            Value::List(vec![Value::Number(i64::MAX - 1), Value::Number(i64::MAX)])
+        );
+        assert_eq!(
+            execute_interpreter("9223372036854775807 in 0..=9223372036854775807"),
+            Value::Boolean(true)
         );
     }
 
     #[test]
-    fn equality() {
-        assert_eq!(execute_interpreter("5 == 5"), Value::Boolean(true));
-        assert_eq!(execute_interpreter("5 == 4"), Value::Boolean(false));
-        assert_eq!(execute_interpreter("true == true"), Value::Boolean(true));
-        assert_eq!(execute_interpreter("true == false"), Value::Boolean(false));
-        assert_eq!(execute_interpreter("false == false"), Value::Boolean(true));
-        assert_eq!(execute_interpreter("5 == true"), Value::Boolean(false));
+    #[should_panic(expected = "has too many items")]
+    fn range_with_too_many_items() {
+        execute_interpreter("-9223372036854775807 - 1..=9223372036854775807");
     }
 
     #[test]
-    fn comparators() {
-        assert_eq!(execute_interpreter("5 > 3"), Value::Boolean(true));
-        assert_eq!(execute_interpreter("5 < 3"), Value::Boolean(false));
-        assert_eq!(execute_interpreter("0 > 3"), Value::Boolean(false));
-        assert_eq!(execute_interpreter("0 < 3"), Value::Boolean(true));
+    #[should_panic(expected = "Cannot redeclare constant 'x'")]
+    fn redeclaring_a_constant() {
+        execute_interpreter("fix x = 1; remember x = 2; x");
     }
 
     #[test]
-    #[should_panic]
-    fn comparators_invalid() {
-        execute_interpreter("5 > true");
+    #[should_panic(expected = "Cannot redeclare constant 'f'")]
+    fn function_named_like_a_constant() {
+        execute_interpreter("fix f = 1; function f() { 2 }; f");
     }
 
     #[test]
-    fn strings() {
+    fn constants_can_be_shadowed_in_inner_scopes() {
         assert_eq!(
-            execute_interpreter("\"hello\""),
-            Value::String("hello".to_string())
+            execute_interpreter("fix x = 1; { remember x = 2; x } + x"),
+            Value::Number(3)
         );
-        assert_eq!(
-            execute_interpreter("remember x = \"hello\"; x;"),
-            Value::String("hello".to_string())
-        )
     }
 
     #[test]
-    #[should_panic]
-    fn invalid_string() {
-        execute_interpreter("\"test");
+    #[should_panic(expected = "Cannot assign to constant 'i'")]
+    fn leaky_loops_respect_constants() {
+        execute_with_options(
+            "fix i = 0; for (i in [1]) { i }",
+            Options {
+                leaky_loops: true,
+                ..Options::default()
+            },
+        );
     }
 
     #[test]
-    fn while_loop() {
-        assert_eq!(
-            execute_interpreter("remember x = 0; while (x < 5) { x = x + 1 }; x;"),
-            Value::Number(5)
-        )
+    fn declared_functions_do_not_keep_their_scope_alive() {
+        let mut program = parser::parse(&tokenizer::tokenize(
+            "function count() { function next(n) { n + 1 }; next(1) }; count()",
+        ));
+        let env = Environment::new(None);
+
+        let result = Interpreter::new(Options::default()).run_program(&mut program, &env);
+
+        assert_eq!(result, Value::Number(2));
+        assert_eq!(Rc::strong_count(&env), 1);
     }
 
     #[test]
-    fn null() {
-        assert_eq!(execute_interpreter("null"), Value::Null)
+    fn returned_functions_keep_their_scope_alive() {
+        assert_eq!(
+            execute_interpreter(
+                "function counter() { remember n = 0; function next() { n += 1; n }; next }; remember next = counter(); next(); next()"
+            ),
+            Value::Number(2)
+        );
     }
 
     #[test]
-    fn lists() {
+    fn methods_of_shadowed_structs() {
         assert_eq!(
-            execute_interpreter("[1, 2, 3]"),
-            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(3)])
+            execute_interpreter(
+                "struct P {}; impl P { function m(self) { 1 } };
+                function make() { struct P {}; impl P { function m(self) { 2 } }; P {} };
+                make().m() * 10 + P {}.m()"
+            ),
+            Value::Number(21)
         );
+    }
+
+    #[test]
+    fn methods_of_redeclared_structs() {
         assert_eq!(
-            execute_interpreter("remember x = [1, 2, 3]; x;"),
-            Value::List(vec![Value::Number(1), Value::Number(2), Value::Number(3)])
-        )
+            execute_interpreter(
+                "struct P {}; impl P { function m(self) { 1 } }; remember p = P {};
+                struct P {}; impl P { function m(self) { 2 } };
+                p.m() * 10 + P {}.m()"
+            ),
+            Value::Number(12)
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn invalid_list_missing_closing_bracket() {
-        execute_interpreter("[1,2");
+    #[should_panic(expected = "Repetition too large in '\"ab\" * 9223372036854775807' at 1:1")]
+    fn string_repetition_too_large() {
+        execute_interpreter("\"ab\" * 9223372036854775807");
     }
 
     #[test]
-    #[should_panic]
-    fn invalid_list_missing_comma() {
-        execute_interpreter("[1 2]");
+    #[should_panic(expected = "Repetition too large in '[1, 2] * 4611686018427387904' at 1:1")]
+    fn list_repetition_too_large() {
+        execute_interpreter("[1, 2] * 4611686018427387904");
     }
 
     #[test]
-    fn for_loop() {
+    fn empty_repetitions() {
         assert_eq!(
-            execute_interpreter("for (x in [1]) { yell(x); };"),
-            Value::Null
+            execute_interpreter("[\"\" * 9223372036854775807, [] * 9223372036854775807]"),
+            Value::List(vec![Value::String(String::new()), Value::List(vec![])])
         );
-
-        assert_eq!(
-            execute_interpreter("remember x = 0; for (y in [5, 6]) { x = y }; y;"),
-            Value::Number(6)
-        )
     }
 
     #[test]
-    fn float() {
-        assert_eq!(execute_interpreter("5.5"), Value::Float(5.5))
+    fn nested_declarations_keep_their_siblings_apart() {
+        assert_eq!(
+            execute_interpreter(
+                "remember a = 1; if (true) { remember b = 2; remember c = 3 }; remember d = 4; [a, d]"
+            ),
+            Value::List(vec![Value::Number(1), Value::Number(4)])
+        );
+        assert_eq!(
+            execute_interpreter(
+                "remember a = 1; if ((remember b = a == 1)) { remember c = 3 }; remember d = 4; [a, b, d]"
+            ),
+            Value::List(vec![
+                Value::Number(1),
+                Value::Boolean(true),
+                Value::Number(4)
+            ])
+        );
+        assert_eq!(
+            execute_interpreter("remember b = (remember c = 7); remember y = 2; [b, c, y]"),
+            Value::List(vec![Value::Number(7), Value::Number(7), Value::Number(2)])
+        );
     }
 
     #[test]
-    fn functions() {
+    fn skipped_declarations_keep_their_siblings_apart() {
         assert_eq!(
-            execute_interpreter("function test() { 5 }; test()"),
-            Value::Number(5)
+            execute_interpreter(
+                "remember a = false && (remember b = 1); remember list = [1]; { list[0] = 5; list += [6] }; [a, list]"
+            ),
+            Value::List(vec![
+                Value::Boolean(false),
+                Value::List(vec![Value::Number(5), Value::Number(6)])
+            ])
         );
     }
 
     #[test]
-    fn functions_with_params() {
-        assert_eq!(
-            execute_interpreter("function test(x) { x + 5 }; test(2)"),
-            Value::Number(7)
-        );
+    fn skipped_defaults_keep_the_parameters_after_them() {
         assert_eq!(
-            execute_interpreter("function add(a, b) { a + b }; add(5, 5)"),
+            execute_interpreter(
+                "function f(a = (remember z = 1), b = 2) { a + b }; remember c = f(5); c + f()"
+            ),
             Value::Number(10)
-        )
+        );
     }
 
     #[test]
-    fn functions_variable_scope() {
+    #[should_panic(expected = "Maximum call depth 18446744073709551615 is too large")]
+    fn call_depth_too_large_for_a_stack() {
+        Options {
+            max_call_depth: usize::MAX,
+            ..Options::default()
+        }
+        .stack_size();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Cannot start the interpreter with a stack of 4611686018427387904 bytes"
+    )]
+    fn stack_too_large_to_start() {
+        run_on_stack(1 << 62, || ());
+    }
+
+    #[test]
+    fn ranges_with_the_same_items_are_equal() {
         assert_eq!(
-            execute_interpreter("remember x = 5; function test(x) { x  + 5 }; test(2)"),
-            Value::Number(7)
+            execute_interpreter("[0..10 step 3 == 0..11 step 3, 0..10 == 0..=10]"),
+            Value::List(vec![Value::Boolean(true), Value::Boolean(false)])
         );
-        assert_eq!(
-            execute_interpreter("remember y = 5; function test(x) { x  + y }; test(2)"),
-            Value::Number(7)
-        )
     }
 }
@@ -1,20 +1,118 @@
-use std::env;
+use std::{env, fs, process};
 
+mod bigint;
+mod builtins;
 mod cursor;
+mod decimal;
+mod dump;
 mod enums;
 mod environment;
 mod examples;
+mod instance;
 mod interpreter;
+mod map;
 mod parser;
+mod printer;
+mod range;
+mod resolver;
 mod tokenizer;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
 
-    if let Some(code_to_execute) = args.get(1) {
-        let result = interpreter::execute_interpreter(code_to_execute);
-        println!("{:?}", result);
-    } else {
-        println!("Usage: cargo run -- \"<your code here>\"");
+    // These flags can be given anywhere, they only change how programs run
+    let mut options = interpreter::Options::default();
+    args.retain(|argument| match argument.as_str() {
+        "--strict" => {
+            options.strict = true;
+            false
+        }
+        "--leaky-loops" => {
+            options.leaky_loops = true;
+            false
+        }
+        _ => match argument.strip_prefix("--max-call-depth=") {
+            Some(depth) => {
+                options.max_call_depth = depth
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Invalid maximum call depth {}", depth));
+                false
+            }
+            None => true,
+        },
+    });
+
+    match args.get(1).map(String::as_str) {
+        Some("fmt") => format_files(&args[2..]),
+        Some(flag) if flag.starts_with("--dump-tokens=") && args.len() > 2 => {
+            print!(
+                "{}",
+                dump::dump_tokens(&args[2], &flag["--dump-tokens=".len()..])
+            );
+        }
+        Some(flag) if flag.starts_with("--dump-ast=") && args.len() > 2 => {
+            print!("{}", dump::dump_ast(&args[2], &flag["--dump-ast=".len()..]));
+        }
+        Some("--print") if args.len() > 2 => {
+            print!(
+                "{}",
+                printer::print(&parser::parse(&tokenizer::tokenize(&args[2])))
+            );
+        }
+        Some(path) if path.ends_with(".nali") => {
+            interpreter::run_on_stack(options.stack_size(), || {
+                let result = interpreter::execute_file(path, options);
+                println!("{:?}", result);
+            });
+        }
+        Some(code_to_execute) => {
+            interpreter::run_on_stack(options.stack_size(), || {
+                let result = interpreter::execute_with_options(code_to_execute, options);
+                println!("{:?}", result);
+            });
+        }
+        None => {
+            println!("Usage: cargo run -- \"<your code here>\"");
+            println!("       cargo run -- <file>.nali");
+            println!("       cargo run -- --strict \"<your code here>\"|<file>.nali");
+            println!("       cargo run -- --leaky-loops \"<your code here>\"|<file>.nali");
+            println!("       cargo run -- --max-call-depth=<n> \"<your code here>\"|<file>.nali");
+            println!("       cargo run -- fmt [--check] <files>");
+            println!("       cargo run -- --dump-tokens=json|sexpr|dot \"<your code here>\"");
+            println!("       cargo run -- --dump-ast=json|sexpr|dot \"<your code here>\"");
+            println!("       (json has the span of every token and node)");
+        }
+    }
+}
+
+/*
+Formats the given files in place. With --check the files are left alone instead and we exit with
+an error if any of them is not formatted, which is handy in CI.
+ */
+fn format_files(arguments: &[String]) {
+    let check = arguments.iter().any(|argument| argument == "--check");
+    let mut has_unformatted_files = false;
+
+    for path in arguments.iter().filter(|argument| *argument != "--check") {
+        let code = fs::read_to_string(path)
+            .unwrap_or_else(|error| panic!("Error reading file {}: {}", path, error));
+        let formatted = printer::format(&code);
+
+        if formatted == code {
+            continue;
+        }
+
+        if check {
+            println!("{} is not formatted", path);
+            has_unformatted_files = true;
+        } else {
+            fs::write(path, formatted)
+                .unwrap_or_else(|error| panic!("Error writing file {}: {}", path, error));
+            println!("Formatted {}", path);
+        }
+    }
+
+    if has_unformatted_files {
+        process::exit(1);
     }
 }
@@ -0,0 +1,148 @@
+use crate::enums::Value;
+use crate::interpreter::values_equal;
+use std::fmt;
+
+/*
+A map keeps its entries in insertion order so iterating over it is deterministic. Keys are
+compared by value like == does, so 1 and 1.0 are the same key, which is why we can't use a
+HashMap here.
+ */
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(Value, Value)>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map { entries: vec![] }
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| values_equal(entry_key, key))
+            .map(|(_, value)| value)
+    }
+
+    pub fn has(&self, key: &Value) -> bool {
+        self.get(key).is_some()
+    }
+
+    /*
+    Overwriting an existing key keeps its original position
+     */
+    pub fn insert(&mut self, key: Value, value: Value) {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|(entry_key, _)| values_equal(entry_key, &key))
+        {
+            entry.1 = value;
+        } else {
+            self.entries.push((key, value));
+        }
+    }
+
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(entry_key, _)| values_equal(entry_key, key))?;
+
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn keys(&self) -> Vec<Value> {
+        self.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn values(&self) -> Vec<Value> {
+        self.entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    pub fn entries(&self) -> &[(Value, Value)] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/*
+Two maps are equal when they contain the same entries, no matter in which order they were
+inserted.
+ */
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.entries.iter().all(|(key, value)| {
+                other
+                    .get(key)
+                    .is_some_and(|other_value| values_equal(value, other_value))
+            })
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(key, value)| (key, value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insertion_order() {
+        let mut map = Map::new();
+        map.insert(Value::String("b".to_string()), Value::Number(1));
+        map.insert(Value::String("a".to_string()), Value::Number(2));
+        map.insert(Value::String("b".to_string()), Value::Number(3));
+
+        assert_eq!(
+            map.keys(),
+            vec![
+                Value::String("b".to_string()),
+                Value::String("a".to_string())
+            ]
+        );
+        assert_eq!(map.values(), vec![Value::Number(3), Value::Number(2)]);
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let mut left = Map::new();
+        left.insert(Value::Number(1), Value::Boolean(true));
+        left.insert(Value::Number(2), Value::Boolean(false));
+
+        let mut right = Map::new();
+        right.insert(Value::Number(2), Value::Boolean(false));
+        right.insert(Value::Number(1), Value::Boolean(true));
+
+        assert_eq!(left, right);
+
+        right.insert(Value::Number(1), Value::Boolean(false));
+        assert_ne!(left, right);
+    }
+
+    #[test]
+    fn remove() {
+        let mut map = Map::new();
+        map.insert(Value::Number(1), Value::Null);
+
+        assert_eq!(map.remove(&Value::Number(1)), Some(Value::Null));
+        assert_eq!(map.remove(&Value::Number(1)), None);
+        assert!(map.is_empty());
+    }
+}
@@ -1,15 +1,43 @@
 use crate::cursor::Cursor;
 use crate::enums::Token;
-use crate::enums::{Expression, Operation};
+use crate::enums::{
+    Argument, Comparator, Expression, Logical, MatchArm, Operation, Parameter, Pattern,
+};
+use crate::tokenizer::{Location, Position, Source};
 
 #[derive(Debug, PartialEq)]
 pub struct Program {
     pub expressions: Vec<Expression>,
+    // Where each statement starts, empty when the program was parsed from tokens alone
+    pub positions: Vec<Position>,
+}
+
+/*
+The tokens something was parsed from. The span of a statement includes the semicolon ending it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
 }
 
 struct Parser<'a> {
     tokens: &'a [Token],
+    locations: &'a [Location],
     position: usize,
+    statements: Vec<Span>,
+    nodes: Vec<Span>,
+}
+
+/*
+Where the parts of a program come from. Statements, methods and match arms are in the order they
+start in the source code. Nodes are every expression, pattern, argument, parameter, map entry,
+struct field, enum variant and match arm as well as the program itself, each recorded once it has
+been parsed completely, so the nodes inside come before the one containing them.
+ */
+pub struct Spans {
+    pub statements: Vec<Span>,
+    pub nodes: Vec<Span>,
 }
 
 impl Cursor<Token> for Parser<'_> {
@@ -26,10 +54,35 @@ impl Cursor<Token> for Parser<'_> {
     }
 }
 
+/*
+Parses tokens without knowing where they are in the source code, so the statements get no
+positions
+ */
 pub fn parse(tokens: &[Token]) -> Program {
+    parse_located(tokens, &[]).0
+}
+
+/*
+Parses the tokens of a source, with the position of every statement
+ */
+pub fn parse_source(source: &Source) -> Program {
+    parse_located(&source.tokens, &source.locations).0
+}
+
+/*
+Parses the tokens of a source and also returns where everything in the program comes from
+ */
+pub fn parse_spans(source: &Source) -> (Program, Spans) {
+    parse_located(&source.tokens, &source.locations)
+}
+
+fn parse_located(tokens: &[Token], locations: &[Location]) -> (Program, Spans) {
     let mut parser = Parser {
         tokens,
+        locations,
         position: 0,
+        statements: vec![],
+        nodes: vec![],
     };
     let ast = parser.parse_program();
 
@@ -37,144 +90,403 @@ pub fn parse(tokens: &[Token]) -> Program {
         panic!("Has not parsed the entire expression")
     }
 
-    ast
+    let spans = Spans {
+        statements: parser.statements,
+        nodes: parser.nodes,
+    };
+
+    (ast, spans)
 }
 
 impl<'a> Parser<'a> {
     fn parse_program(&mut self) -> Program {
         let mut program = Program {
             expressions: vec![],
+            positions: vec![],
         };
 
         while self.position < self.tokens.len() {
-            let statement = self.parse_statement();
+            program.positions.extend(self.statement_position());
+            let span = self.start_statement();
+            let statement = match self.tokens.get(self.position) {
+                Some(Token::Import) => self.parse_import(),
+                Some(Token::Export) => self.parse_export(),
+                _ => self.parse_statement(),
+            };
             program.expressions.push(statement);
 
             match self.tokens.get(self.position) {
                 Some(Token::Semicolon) => self.advance(1),
-                None => break,
+                None => {}
                 _ => panic!("Expected ';' between statements"),
             }
+
+            self.statements[span].end = self.position;
         }
 
-        program
+        self.node(0, program)
+    }
+
+    /*
+    Statements are recorded when they start, so nested statements come after the one containing
+    them. The end is filled in once the statement has been parsed.
+     */
+    fn start_statement(&mut self) -> usize {
+        self.statements.push(Span {
+            start: self.position,
+            end: self.position,
+        });
+
+        self.statements.len() - 1
+    }
+
+    fn statement_position(&self) -> Option<Position> {
+        self.locations
+            .get(self.position)
+            .map(|location| location.start)
+    }
+
+    /*
+    Records the span of a node from the given start up to the current position, once everything
+    inside the node has been parsed
+     */
+    fn node<T>(&mut self, start: usize, node: T) -> T {
+        self.nodes.push(Span {
+            start,
+            end: self.position,
+        });
+
+        node
     }
 
     fn parse_statement(&mut self) -> Expression {
         match self.tokens.get(self.position) {
-            Some(Token::Remember) => self.parse_declaration(),
+            Some(Token::Remember) | Some(Token::Fix) => self.parse_declaration(),
             Some(Token::Yell) => self.parse_yell(),
             Some(Token::If) => self.parse_if(),
             Some(Token::While) => self.parse_while(),
             Some(Token::For) => self.parse_for(),
             Some(Token::Function) => self.parse_function(),
+            Some(Token::Struct) => self.parse_struct(),
+            Some(Token::Impl) => self.parse_impl(),
+            Some(Token::Enum) => self.parse_enum(),
+            Some(Token::Import) => panic!(
+                "import is only allowed at the top level of a module, found at position {}",
+                self.position
+            ),
+            Some(Token::Export) => panic!(
+                "export is only allowed at the top level of a module, found at position {}",
+                self.position
+            ),
             _ => self.parse_assignment(),
         }
     }
 
-    fn parse_declaration(&mut self) -> Expression {
-        self.consume(&Token::Remember); // Consume remember
+    /*
+    An import either brings every export of a module into scope or only the listed ones:
+        Import -> path
+        Import -> BlockOpen -> identifier (Comma -> identifier)* -> BlockClosed -> from -> path
+     */
+    fn parse_import(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::Import);
 
-        let name = match self.get_current() {
-            Token::Identifier(name) => {
-                self.advance(1);
-                name.clone()
+        let names = match self.get_current() {
+            Token::BlockOpen => {
+                self.consume(&Token::BlockOpen);
+
+                let mut names = vec![];
+                while self.get_current() != Token::BlockClosed {
+                    names.push(self.parse_identifier());
+
+                    match self.get_current() {
+                        Token::BlockClosed => break,
+                        Token::Comma => self.consume(&Token::Comma),
+                        _ => panic!(
+                            "Unexpected token {:?} at position {}",
+                            self.get_current(),
+                            self.position
+                        ),
+                    }
+                }
+
+                self.consume(&Token::BlockClosed);
+
+                match self.get_current() {
+                    Token::Identifier(identifier) if identifier == "from" => self.advance(1),
+                    _ => panic!("Expected from after the imported names"),
+                }
+
+                Some(names)
             }
+            _ => None,
+        };
+
+        let path = match self.get_current() {
+            Token::Quote => match self.parse_factor() {
+                Expression::String(path) => path,
+                _ => unreachable!(),
+            },
+            _ => panic!("Expected the path of the module to import as a string"),
+        };
+        // The path is part of the import rather than a node of its own
+        self.nodes.pop();
+
+        self.node(start, Expression::Import { path, names })
+    }
+
+    /*
+    Only declarations can be exported:
+        Export -> (function | remember | fix | struct | enum)
+     */
+    fn parse_export(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::Export);
+
+        match self.get_current() {
+            Token::Function | Token::Remember | Token::Fix | Token::Struct | Token::Enum => {
+                let expression = self.parse_statement();
+
+                self.node(
+                    start,
+                    Expression::Export {
+                        expression: Box::new(expression),
+                    },
+                )
+            }
+            _ => panic!(
+                "Only declarations can be exported, found {:?} at position {}",
+                self.get_current(),
+                self.position
+            ),
+        }
+    }
+
+    /*
+    A declaration binds a new variable, or a constant when using fix:
+        (Remember | Fix) -> pattern -> Equals -> expression
+     */
+    fn parse_declaration(&mut self) -> Expression {
+        let start = self.position;
+        let constant = self.get_current() == Token::Fix;
+        self.advance(1); // Consume remember or fix
+
+        let pattern = match self.get_current() {
+            Token::Identifier(_) | Token::BracketOpen => self.parse_pattern(),
             _ => panic!("Expected identifier after remember"),
         };
 
         self.consume(&Token::Equals);
 
-        let value = self.parse_expression();
+        let value = self.parse_or();
 
-        Expression::Assign {
-            name,
-            value: Box::new(value),
-        }
+        self.node(
+            start,
+            Expression::Declare {
+                pattern,
+                value: Box::new(value),
+                constant,
+            },
+        )
+    }
+
+    /*
+    A pattern is an identifier, a wildcard, a literal, an enum variant or a list of patterns which
+    may end in a rest binding:
+        identifier | _ | literal | Enum::Variant(pattern, ...) | [pattern, pattern, ...identifier]
+     */
+    fn parse_pattern(&mut self) -> Pattern {
+        let start = self.position;
+
+        let pattern = match self.get_current() {
+            Token::Identifier(identifier) if identifier == "_" => {
+                self.advance(1);
+                Pattern::Wildcard
+            }
+            Token::Identifier(identifier)
+                if self.tokens.get(self.position + 1) != Some(&Token::DoubleColon) =>
+            {
+                self.advance(1);
+                Pattern::Identifier(identifier)
+            }
+            Token::Identifier(_) => {
+                let (identifier, variant) = self.parse_variant_path();
+
+                let mut payload = vec![];
+                if self.tokens.get(self.position) == Some(&Token::ParenthesesOpen) {
+                    self.consume(&Token::ParenthesesOpen);
+
+                    while self.get_current() != Token::ParenthesesClosed {
+                        payload.push(self.parse_pattern());
+
+                        match self.get_current() {
+                            Token::ParenthesesClosed => break,
+                            Token::Comma => self.consume(&Token::Comma),
+                            _ => panic!(
+                                "Unexpected token {:?} at position {}",
+                                self.get_current(),
+                                self.position
+                            ),
+                        }
+                    }
+
+                    self.consume(&Token::ParenthesesClosed);
+                }
+
+                Pattern::Variant {
+                    identifier,
+                    variant,
+                    payload,
+                }
+            }
+            Token::Number(_)
+            | Token::Float(_)
+            | Token::BigInt(_)
+            | Token::Decimal(_)
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::Quote => Pattern::Literal(Box::new(self.parse_factor())),
+            Token::BracketOpen => {
+                self.consume(&Token::BracketOpen);
+
+                let mut items = vec![];
+                let mut rest = None;
+
+                while self.get_current() != Token::BracketClosed {
+                    if self.get_current() == Token::Ellipsis {
+                        self.consume(&Token::Ellipsis);
+
+                        match self.get_current() {
+                            Token::Identifier(identifier) => {
+                                self.advance(1);
+                                rest = Some(identifier);
+                            }
+                            _ => panic!(
+                                "Invalid token ${:?} at position {}",
+                                self.get_current(),
+                                self.position
+                            ),
+                        }
+
+                        // The rest binding always has to be the last item of the pattern
+                        break;
+                    }
+
+                    items.push(self.parse_pattern());
+
+                    match self.get_current() {
+                        Token::BracketClosed => break,
+                        Token::Comma => self.consume(&Token::Comma),
+                        _ => panic!(
+                            "Unexpected token {:?} at position {}",
+                            self.get_current(),
+                            self.position
+                        ),
+                    }
+                }
+
+                self.consume(&Token::BracketClosed);
+
+                Pattern::List { items, rest }
+            }
+            _ => panic!(
+                "Invalid token ${:?} at position {}",
+                self.get_current(),
+                self.position
+            ),
+        };
+
+        self.node(start, pattern)
     }
 
     fn parse_yell(&mut self) -> Expression {
+        let start = self.position;
         self.consume(&Token::Yell);
         self.consume(&Token::ParenthesesOpen);
-        let expression = self.parse_expression();
+        let expression = self.parse_or();
         self.consume(&Token::ParenthesesClosed);
 
-        Expression::Yell {
-            expression: Box::new(expression),
-        }
+        self.node(
+            start,
+            Expression::Yell {
+                expression: Box::new(expression),
+            },
+        )
     }
 
     fn parse_if(&mut self) -> Expression {
+        let start = self.position;
         self.consume(&Token::If);
 
         self.consume(&Token::ParenthesesOpen);
-        let condition = self.parse_comparator();
+        let condition = self.parse_or();
         self.consume(&Token::ParenthesesClosed);
 
         let success_expression = self.parse_block();
 
-        if self.tokens.get(self.position) == Some(&Token::Else) {
+        let failure_expression = if self.tokens.get(self.position) == Some(&Token::Else) {
             self.consume(&Token::Else);
 
-            let failure_expression = self.parse_block();
-
-            Expression::If {
-                condition: Box::new(condition),
-                success_expression: Box::new(success_expression),
-                failure_expression: Some(Box::new(failure_expression)),
-            }
+            Some(Box::new(self.parse_block()))
         } else {
+            None
+        };
+
+        self.node(
+            start,
             Expression::If {
                 condition: Box::new(condition),
                 success_expression: Box::new(success_expression),
-                failure_expression: None,
-            }
-        }
+                failure_expression,
+            },
+        )
     }
 
     fn parse_for(&mut self) -> Expression {
+        let start = self.position;
         self.consume(&Token::For);
         self.consume(&Token::ParenthesesOpen);
 
-        let name = match self.get_current() {
-            Token::Identifier(identifier) => identifier,
-            _ => panic!(
-                "Invalid token ${:?} at position {}",
-                self.get_current(),
-                self.position
-            ),
-        };
-        self.consume(&Token::Identifier(name.clone()));
+        let pattern = self.parse_pattern();
         self.consume(&Token::In);
 
-        let list = self.parse_factor();
+        let list = self.parse_pipeline();
 
         self.consume(&Token::ParenthesesClosed);
 
         let expression = self.parse_expression();
 
-        Expression::For {
-            identifier: name,
-            list: Box::new(list),
-            expression: Box::new(expression),
-        }
+        self.node(
+            start,
+            Expression::For {
+                pattern,
+                list: Box::new(list),
+                expression: Box::new(expression),
+            },
+        )
     }
 
     fn parse_while(&mut self) -> Expression {
+        let start = self.position;
         self.consume(&Token::While);
         self.consume(&Token::ParenthesesOpen);
-        let condition = self.parse_comparator();
+        let condition = self.parse_or();
         self.consume(&Token::ParenthesesClosed);
 
         let expression = self.parse_block();
 
-        Expression::While {
-            condition: Box::new(condition),
-            expression: Box::new(expression),
-        }
+        self.node(
+            start,
+            Expression::While {
+                condition: Box::new(condition),
+                expression: Box::new(expression),
+            },
+        )
     }
 
     fn parse_function(&mut self) -> Expression {
+        let start = self.position;
         self.consume(&Token::Function);
 
         let identifier = match self.get_current() {
@@ -189,20 +501,116 @@ impl<'a> Parser<'a> {
         self.consume(&Token::Identifier(identifier.clone()));
         self.consume(&Token::ParenthesesOpen);
 
-        let mut parameters: Vec<String> = vec![];
+        let mut parameters: Vec<Parameter> = vec![];
         while self.get_current() != Token::ParenthesesClosed {
+            if parameters
+                .last()
+                .is_some_and(|parameter| parameter.variadic)
+            {
+                panic!(
+                    "The rest parameter of function {} has to be the last one",
+                    identifier
+                );
+            }
+
+            parameters.push(self.parse_parameter());
+
             match self.get_current() {
-                Token::Identifier(identifier) => parameters.push(identifier),
+                Token::ParenthesesClosed => break,
+                Token::Comma => self.consume(&Token::Comma),
                 _ => panic!(
-                    "Invalid token ${:?} at position {}",
+                    "Unexpected token {:?} at position {}",
                     self.get_current(),
                     self.position
                 ),
             }
-            self.advance(1);
+        }
+
+        self.consume(&Token::ParenthesesClosed);
+
+        let expression = self.parse_block();
+
+        self.node(
+            start,
+            Expression::Function {
+                identifier,
+                parameters,
+                expression: Box::new(expression),
+            },
+        )
+    }
+
+    /*
+    A parameter is a pattern with an optional default value, or a rest parameter collecting all
+    remaining arguments:
+        pattern [-> Equals -> expression] | Ellipsis -> pattern
+     */
+    fn parse_parameter(&mut self) -> Parameter {
+        let start = self.position;
+
+        if self.get_current() == Token::Ellipsis {
+            self.consume(&Token::Ellipsis);
+            let pattern = self.parse_pattern();
+
+            return self.node(
+                start,
+                Parameter {
+                    pattern,
+                    default: None,
+                    variadic: true,
+                },
+            );
+        }
+
+        let pattern = self.parse_pattern();
+
+        let default = if self.get_current() == Token::Equals {
+            self.consume(&Token::Equals);
+            Some(self.parse_or())
+        } else {
+            None
+        };
+
+        self.node(
+            start,
+            Parameter {
+                pattern,
+                default,
+                variadic: false,
+            },
+        )
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        match self.get_current() {
+            Token::Identifier(identifier) => {
+                self.advance(1);
+                identifier
+            }
+            _ => panic!(
+                "Invalid token ${:?} at position {}",
+                self.get_current(),
+                self.position
+            ),
+        }
+    }
+
+    /*
+    A struct declaration lists the names of its fields:
+        struct Point { x, y }
+     */
+    fn parse_struct(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::Struct);
+        let identifier = self.parse_identifier();
+        self.consume(&Token::BlockOpen);
+
+        let mut fields = vec![];
+        while self.get_current() != Token::BlockClosed {
+            fields.push(self.parse_identifier());
 
             match self.get_current() {
-                Token::ParenthesesClosed => break,
+                Token::BlockClosed => break,
                 Token::Comma => self.consume(&Token::Comma),
                 _ => panic!(
                     "Unexpected token {:?} at position {}",
@@ -212,40 +620,78 @@ impl<'a> Parser<'a> {
             }
         }
 
-        self.consume(&Token::ParenthesesClosed);
+        self.consume(&Token::BlockClosed);
 
-        let expression = self.parse_block();
+        self.node(start, Expression::Struct { identifier, fields })
+    }
+
+    /*
+    An impl block attaches methods to a struct. Every method receives the instance it is called
+    on through its first parameter, which has to be called self:
+        impl Point { function length(self) { ... } }
+     */
+    fn parse_impl(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::Impl);
+        let identifier = self.parse_identifier();
+        self.consume(&Token::BlockOpen);
+
+        let mut methods = vec![];
+        while self.get_current() != Token::BlockClosed {
+            let span = self.start_statement();
+            let method = self.parse_function();
+
+            if let Expression::Function { parameters, .. } = &method
+                && parameters.first().map(|parameter| &parameter.pattern)
+                    != Some(&Pattern::Identifier("self".to_string()))
+            {
+                panic!(
+                    "Methods of {} need to take self as their first parameter",
+                    identifier
+                );
+            }
+
+            methods.push(method);
+
+            if self.get_current() == Token::Semicolon {
+                self.consume(&Token::Semicolon);
+            }
 
-        Expression::Function {
-            identifier,
-            parameters,
-            expression: Box::new(expression),
+            self.statements[span].end = self.position;
         }
+
+        self.consume(&Token::BlockClosed);
+
+        self.node(
+            start,
+            Expression::Impl {
+                identifier,
+                methods,
+            },
+        )
     }
 
     /*
-    An assignment follows this pattern:
-        identifier -> Equals -> expression
+    An enum lists its variants, each of which can carry a payload:
+        enum Status { Pending, Done(result), Failed(reason) }
      */
-    fn parse_assignment(&mut self) -> Expression {
-        let expression = self.parse_comparator();
+    fn parse_enum(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::Enum);
+        let identifier = self.parse_identifier();
+        self.consume(&Token::BlockOpen);
 
-        if let Expression::Variable(ref name) = expression {
-            if let Some(Token::Equals) = self.tokens.get(self.position) {
-                self.advance(1);
-                let value = self.parse_assignment();
-                return Expression::Assign {
-                    name: name.clone(),
-                    value: Box::new(value),
-                };
-            }
+        let mut variants = vec![];
+        while self.get_current() != Token::BlockClosed {
+            let variant_start = self.position;
+            let variant = self.parse_identifier();
 
-            if let Some(Token::ParenthesesOpen) = self.tokens.get(self.position) {
-                self.advance(1);
+            let mut payload = vec![];
+            if self.get_current() == Token::ParenthesesOpen {
+                self.consume(&Token::ParenthesesOpen);
 
-                let mut parameters: Vec<Expression> = vec![];
                 while self.get_current() != Token::ParenthesesClosed {
-                    parameters.push(self.parse_expression());
+                    payload.push(self.parse_identifier());
 
                     match self.get_current() {
                         Token::ParenthesesClosed => break,
@@ -259,77 +705,528 @@ impl<'a> Parser<'a> {
                 }
 
                 self.consume(&Token::ParenthesesClosed);
-                return Expression::FunctionCall {
-                    identifier: name.clone(),
-                    parameters,
-                };
             }
-        }
 
-        expression
-    }
-
-    fn parse_comparator(&mut self) -> Expression {
-        let left = self.parse_expression();
+            variants.push(self.node(variant_start, (variant, payload)));
 
-        if let Some(Token::Comparator(comparator)) = self.tokens.get(self.position) {
-            self.advance(1);
-            let right = self.parse_expression();
-
-            Expression::Comparison {
-                left: Box::new(left),
-                comparator: *comparator,
-                right: Box::new(right),
+            match self.get_current() {
+                Token::BlockClosed => break,
+                Token::Comma => self.consume(&Token::Comma),
+                _ => panic!(
+                    "Unexpected token {:?} at position {}",
+                    self.get_current(),
+                    self.position
+                ),
             }
-        } else {
-            left
         }
+
+        self.consume(&Token::BlockClosed);
+
+        self.node(
+            start,
+            Expression::Enum {
+                identifier,
+                variants,
+            },
+        )
     }
 
-    fn parse_expression(&mut self) -> Expression {
-        // We instantly resolve left
-        let mut left = self.parse_term();
+    fn parse_variant_path(&mut self) -> (String, String) {
+        let identifier = self.parse_identifier();
+        self.consume(&Token::DoubleColon);
+        let variant = self.parse_identifier();
 
-        // Iterate over tokens while you still have operations left
-        while let Some(Token::Operation(operation)) = self.tokens.get(self.position) {
-            match operation {
-                Operation::Add | Operation::Subtract => {
-                    self.advance(1);
+        (identifier, variant)
+    }
 
-                    // We instantly resolve right
-                    let right = self.parse_term();
+    /*
+    The payload of a variant is passed like the arguments of a function, but only by position:
+        Enum::Variant(expression, ...)
+     */
+    fn parse_variant(&mut self) -> Expression {
+        let start = self.position;
+        let (identifier, variant) = self.parse_variant_path();
+
+        let mut parameters = vec![];
+        if self.tokens.get(self.position) == Some(&Token::ParenthesesOpen) {
+            self.consume(&Token::ParenthesesOpen);
+
+            while self.get_current() != Token::ParenthesesClosed {
+                if let (Token::Ellipsis, _) | (Token::Identifier(_), Some(Token::Equals)) =
+                    (self.get_current(), self.tokens.get(self.position + 1))
+                {
+                    panic!(
+                        "The payload of {}::{} can only be passed as positional values",
+                        identifier, variant
+                    );
+                }
 
-                    left = Expression::Binary {
-                        left: Box::new(left),
-                        operation: *operation,
-                        right: Box::new(right),
-                    };
+                parameters.push(self.parse_or());
+
+                match self.get_current() {
+                    Token::ParenthesesClosed => break,
+                    Token::Comma => self.consume(&Token::Comma),
+                    _ => panic!(
+                        "Unexpected token {:?} at position {}",
+                        self.get_current(),
+                        self.position
+                    ),
                 }
-                _ => break,
             }
+
+            self.consume(&Token::ParenthesesClosed);
         }
 
-        left
+        self.node(
+            start,
+            Expression::Variant {
+                identifier,
+                variant,
+                parameters,
+            },
+        )
+    }
+
+    /*
+    A match compares a value against a list of arms and evaluates the first one whose pattern
+    matches and whose optional guard holds:
+        match (value) { pattern if (guard) => expression, _ => expression }
+     */
+    fn parse_match(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::Match);
+        self.consume(&Token::ParenthesesOpen);
+        let value = self.parse_or();
+        self.consume(&Token::ParenthesesClosed);
+        self.consume(&Token::BlockOpen);
+
+        let mut arms = vec![];
+        while self.get_current() != Token::BlockClosed {
+            let span = self.start_statement();
+            let pattern = self.parse_pattern();
+
+            let guard = if self.get_current() == Token::If {
+                self.consume(&Token::If);
+                self.consume(&Token::ParenthesesOpen);
+                let guard = self.parse_or();
+                self.consume(&Token::ParenthesesClosed);
+                Some(guard)
+            } else {
+                None
+            };
+
+            self.consume(&Token::FatArrow);
+            let expression = self.parse_statement();
+
+            arms.push(self.node(
+                self.statements[span].start,
+                MatchArm {
+                    pattern,
+                    guard,
+                    expression,
+                },
+            ));
+
+            match self.get_current() {
+                Token::BlockClosed => {}
+                Token::Comma => self.consume(&Token::Comma),
+                _ => panic!(
+                    "Unexpected token {:?} at position {}",
+                    self.get_current(),
+                    self.position
+                ),
+            }
+
+            self.statements[span].end = self.position;
+        }
+
+        self.consume(&Token::BlockClosed);
+
+        self.node(
+            start,
+            Expression::Match {
+                value: Box::new(value),
+                arms,
+            },
+        )
+    }
+
+    /*
+    An assignment follows this pattern:
+        identifier -> Equals -> expression
+    or, when destructuring:
+        pattern -> Equals -> expression
+    or, for compound assignments to a variable, index or field:
+        place -> CompoundAssign -> expression | place -> Increment | place -> Decrement
+     */
+    fn parse_assignment(&mut self) -> Expression {
+        let start = self.position;
+
+        if self.is_destructuring_assignment() {
+            let pattern = self.parse_pattern();
+            self.consume(&Token::Equals);
+            let value = self.parse_assignment();
+
+            return self.node(
+                start,
+                Expression::Destructure {
+                    pattern,
+                    value: Box::new(value),
+                },
+            );
+        }
+
+        let expression = self.parse_or();
+
+        if let Expression::Variable(_) | Expression::Index { .. } | Expression::Field { .. } =
+            expression
+        {
+            let compound = match self.tokens.get(self.position) {
+                Some(Token::CompoundAssign(operation)) => {
+                    let operation = *operation;
+                    self.advance(1);
+                    Some((operation, self.parse_assignment()))
+                }
+                // The one added or subtracted is spanned by the operator
+                Some(Token::Increment) => {
+                    self.advance(1);
+                    Some((
+                        Operation::Add,
+                        self.node(self.position - 1, Expression::Number(1)),
+                    ))
+                }
+                Some(Token::Decrement) => {
+                    self.advance(1);
+                    Some((
+                        Operation::Subtract,
+                        self.node(self.position - 1, Expression::Number(1)),
+                    ))
+                }
+                _ => None,
+            };
+
+            if let Some((operation, value)) = compound {
+                return self.node(
+                    start,
+                    Expression::CompoundAssign {
+                        target: Box::new(expression),
+                        operation,
+                        value: Box::new(value),
+                    },
+                );
+            }
+        }
+
+        // The place assigned to becomes part of the assignment, so it isn't a node of its own
+        if let Expression::Index { target, index } = &expression
+            && let Some(Token::Equals) = self.tokens.get(self.position)
+        {
+            self.nodes.pop();
+            self.advance(1);
+            let value = self.parse_assignment();
+            return self.node(
+                start,
+                Expression::IndexAssign {
+                    target: target.clone(),
+                    index: index.clone(),
+                    value: Box::new(value),
+                },
+            );
+        }
+
+        if let Expression::Field { target, field } = &expression
+            && let Some(Token::Equals) = self.tokens.get(self.position)
+        {
+            self.nodes.pop();
+            self.advance(1);
+            let value = self.parse_assignment();
+            return self.node(
+                start,
+                Expression::FieldAssign {
+                    target: target.clone(),
+                    field: field.clone(),
+                    value: Box::new(value),
+                },
+            );
+        }
+
+        if let Expression::Variable(ref name) = expression
+            && let Some(Token::Equals) = self.tokens.get(self.position)
+        {
+            self.nodes.pop();
+            self.advance(1);
+            let value = self.parse_assignment();
+            return self.node(
+                start,
+                Expression::Assign {
+                    name: name.clone(),
+                    value: Box::new(value),
+                },
+            );
+        }
+
+        expression
+    }
+
+    /*
+    Arguments are passed by position, by name or spread from a list:
+        expression | identifier -> Equals -> expression | Ellipsis -> expression
+     */
+    fn parse_arguments(&mut self) -> Vec<Argument> {
+        self.consume(&Token::ParenthesesOpen);
+
+        let mut parameters: Vec<Argument> = vec![];
+        while self.get_current() != Token::ParenthesesClosed {
+            let start = self.position;
+            let argument = match (self.get_current(), self.tokens.get(self.position + 1)) {
+                (Token::Ellipsis, _) => {
+                    self.consume(&Token::Ellipsis);
+                    Argument::Spread(self.parse_or())
+                }
+                (Token::Identifier(name), Some(Token::Equals)) => {
+                    self.advance(2);
+                    Argument::Named(name, self.parse_or())
+                }
+                _ => Argument::Positional(self.parse_or()),
+            };
+            parameters.push(self.node(start, argument));
+
+            match self.get_current() {
+                Token::ParenthesesClosed => break,
+                Token::Comma => self.consume(&Token::Comma),
+                _ => panic!(
+                    "Unexpected token {:?} at position {}",
+                    self.get_current(),
+                    self.position
+                ),
+            }
+        }
+
+        self.consume(&Token::ParenthesesClosed);
+
+        parameters
+    }
+
+    /*
+    A list on the left side of an equals sign is a destructuring pattern rather than a list
+    literal, so we look past the matching closing bracket before deciding how to parse it.
+     */
+    fn is_destructuring_assignment(&self) -> bool {
+        if self.tokens.get(self.position) != Some(&Token::BracketOpen) {
+            return false;
+        }
+
+        let mut depth = 0;
+        for (offset, token) in self.tokens[self.position..].iter().enumerate() {
+            match token {
+                Token::BracketOpen => depth += 1,
+                Token::BracketClosed => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return self.tokens.get(self.position + offset + 1) == Some(&Token::Equals);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /*
+    Logical operators bind looser than comparisons, && binds tighter than ||:
+        and -> (Or -> and)*
+        comparison -> (And -> comparison)*
+     */
+    fn parse_or(&mut self) -> Expression {
+        let start = self.position;
+        let mut left = self.parse_and();
+
+        while self.tokens.get(self.position) == Some(&Token::Or) {
+            self.advance(1);
+            let right = self.parse_and();
+            left = self.node(
+                start,
+                Expression::Logical {
+                    left: Box::new(left),
+                    operator: Logical::Or,
+                    right: Box::new(right),
+                },
+            );
+        }
+
+        left
+    }
+
+    fn parse_and(&mut self) -> Expression {
+        let start = self.position;
+        let mut left = self.parse_comparator();
+
+        while self.tokens.get(self.position) == Some(&Token::And) {
+            self.advance(1);
+            let right = self.parse_comparator();
+            left = self.node(
+                start,
+                Expression::Logical {
+                    left: Box::new(left),
+                    operator: Logical::And,
+                    right: Box::new(right),
+                },
+            );
+        }
+
+        left
+    }
+
+    fn parse_comparator(&mut self) -> Expression {
+        let start = self.position;
+        let left = self.parse_pipeline();
+
+        let comparator = match self.tokens.get(self.position) {
+            Some(Token::Comparator(comparator)) => *comparator,
+            Some(Token::In) => Comparator::In,
+            _ => return left,
+        };
+
+        self.advance(1);
+        let right = self.parse_pipeline();
+
+        self.node(
+            start,
+            Expression::Comparison {
+                left: Box::new(left),
+                comparator,
+                right: Box::new(right),
+            },
+        )
+    }
+
+    /*
+    A pipeline passes the value on its left as the first argument of the call on its right. It
+    binds looser than ranges and arithmetic but tighter than comparisons:
+        expression -> Pipe -> identifier -> (arguments) -> Pipe -> ...
+     */
+    fn parse_pipeline(&mut self) -> Expression {
+        let start = self.position;
+        let mut value = self.parse_range();
+
+        while self.tokens.get(self.position) == Some(&Token::Pipe) {
+            self.advance(1);
+            let identifier = self.parse_identifier();
+
+            if self.tokens.get(self.position) != Some(&Token::ParenthesesOpen) {
+                panic!(
+                    "Expected a function call after |> but got {:?} at position {}",
+                    self.tokens.get(self.position),
+                    self.position
+                );
+            }
+
+            let parameters = self.parse_arguments();
+            value = self.node(
+                start,
+                Expression::Pipeline {
+                    value: Box::new(value),
+                    identifier,
+                    parameters,
+                },
+            );
+        }
+
+        value
+    }
+
+    /*
+    A range binds looser than arithmetic, so 0..n + 1 goes up to n + 1. The step is optional:
+        expression -> (DotDot | DotDotEquals) -> expression [-> step -> expression]
+     */
+    fn parse_range(&mut self) -> Expression {
+        let position = self.position;
+        let start = self.parse_expression();
+
+        let inclusive = match self.tokens.get(self.position) {
+            Some(Token::DotDot) => false,
+            Some(Token::DotDotEquals) => true,
+            _ => return start,
+        };
+        self.advance(1);
+
+        let end = self.parse_expression();
+
+        let step = match self.tokens.get(self.position) {
+            Some(Token::Identifier(identifier)) if identifier == "step" => {
+                self.advance(1);
+                Some(Box::new(self.parse_expression()))
+            }
+            _ => None,
+        };
+
+        self.node(
+            position,
+            Expression::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                step,
+                inclusive,
+            },
+        )
+    }
+
+    fn parse_expression(&mut self) -> Expression {
+        let start = self.position;
+
+        // We instantly resolve left
+        let mut left = self.parse_term();
+
+        // Iterate over tokens while you still have operations left
+        while let Some(Token::Operation(operation)) = self.tokens.get(self.position) {
+            match operation {
+                Operation::Add | Operation::Subtract => {
+                    self.advance(1);
+
+                    // We instantly resolve right
+                    let right = self.parse_term();
+
+                    left = self.node(
+                        start,
+                        Expression::Binary {
+                            left: Box::new(left),
+                            operation: *operation,
+                            right: Box::new(right),
+                        },
+                    );
+                }
+                _ => break,
+            }
+        }
+
+        left
     }
 
     fn parse_term(&mut self) -> Expression {
+        let start = self.position;
+
         // We instantly resolve left
         let mut left = self.parse_unary();
 
         // Iterate over tokens while you still have operations left
         while let Some(Token::Operation(operation)) = self.tokens.get(self.position) {
             match operation {
-                Operation::Multiply | Operation::Divide => {
+                Operation::Multiply | Operation::Divide | Operation::Modulo => {
                     self.advance(1);
 
                     // We instantly resolve right
                     let right = self.parse_unary();
 
-                    left = Expression::Binary {
-                        left: Box::new(left),
-                        operation: *operation,
-                        right: Box::new(right),
-                    }
+                    left = self.node(
+                        start,
+                        Expression::Binary {
+                            left: Box::new(left),
+                            operation: *operation,
+                            right: Box::new(right),
+                        },
+                    )
                 }
                 _ => break,
             }
@@ -339,17 +1236,35 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_unary(&mut self) -> Expression {
+        let start = self.position;
+
+        if self.tokens.get(self.position) == Some(&Token::Not) {
+            self.advance(1);
+            let expression = self.parse_unary();
+            return self.node(
+                start,
+                Expression::Not {
+                    expression: Box::new(expression),
+                },
+            );
+        }
+
         let Some(Token::Operation(operation)) = self.tokens.get(self.position) else {
-            return self.parse_factor();
+            return self.parse_postfix();
         };
 
         match operation {
             Operation::Add | Operation::Subtract => {
+                let operation = *operation;
                 self.advance(1);
-                Expression::Unary {
-                    operation: *operation,
-                    expression: Box::new(self.parse_unary()),
-                }
+                let expression = self.parse_unary();
+                self.node(
+                    start,
+                    Expression::Unary {
+                        operation,
+                        expression: Box::new(expression),
+                    },
+                )
             }
             _ => panic!(
                 "Invalid token ${:?} at position {}",
@@ -358,9 +1273,60 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /*
+    Postfix operations bind tighter than anything else:
+        factor -> [index] | .field | .method(arguments) -> ...
+     */
+    fn parse_postfix(&mut self) -> Expression {
+        let start = self.position;
+        let mut expression = self.parse_factor();
+
+        loop {
+            match self.tokens.get(self.position) {
+                Some(Token::BracketOpen) => {
+                    self.advance(1);
+                    let index = self.parse_range();
+                    self.consume(&Token::BracketClosed);
+
+                    expression = self.node(
+                        start,
+                        Expression::Index {
+                            target: Box::new(expression),
+                            index: Box::new(index),
+                        },
+                    );
+                }
+                Some(Token::Dot) => {
+                    self.advance(1);
+                    let name = self.parse_identifier();
+
+                    let postfix =
+                        if let Some(Token::ParenthesesOpen) = self.tokens.get(self.position) {
+                            let parameters = self.parse_arguments();
+                            Expression::MethodCall {
+                                target: Box::new(expression),
+                                method: name,
+                                parameters,
+                            }
+                        } else {
+                            Expression::Field {
+                                target: Box::new(expression),
+                                field: name,
+                            }
+                        };
+                    expression = self.node(start, postfix);
+                }
+                _ => break,
+            }
+        }
+
+        expression
+    }
+
     fn parse_factor(&mut self) -> Expression {
+        let start = self.position;
         let token = self.tokens.get(self.position);
-        match token {
+        let expression = match token {
             Some(Token::Number(n)) => {
                 self.advance(1);
                 Expression::Number(*n)
@@ -369,6 +1335,14 @@ impl<'a> Parser<'a> {
                 self.advance(1);
                 Expression::Float(*f)
             }
+            Some(Token::BigInt(big)) => {
+                self.advance(1);
+                Expression::BigInt(big.clone())
+            }
+            Some(Token::Decimal(decimal)) => {
+                self.advance(1);
+                Expression::Decimal(decimal.clone())
+            }
             Some(Token::True) => {
                 self.advance(1);
                 Expression::Boolean(true)
@@ -382,17 +1356,45 @@ impl<'a> Parser<'a> {
                 Expression::Null
             }
             Some(Token::ParenthesesOpen) => {
+                // Any statement can be wrapped in parentheses to use it as part of an expression
                 self.advance(1);
-                let expression = self.parse_expression();
+                let expression = self.parse_statement();
                 self.consume(&Token::ParenthesesClosed);
 
-                expression
+                // The parentheses only group, the span is the one of what they contain
+                return expression;
             }
             Some(Token::Identifier(name)) => {
+                if self.is_struct_instance() {
+                    return self.parse_struct_instance();
+                }
+
+                if self.tokens.get(self.position + 1) == Some(&Token::DoubleColon) {
+                    return self.parse_variant();
+                }
+
+                if self.tokens.get(self.position + 1) == Some(&Token::ParenthesesOpen) {
+                    self.advance(1);
+                    let parameters = self.parse_arguments();
+                    return self.node(
+                        start,
+                        Expression::FunctionCall {
+                            identifier: name.clone(),
+                            parameters,
+                        },
+                    );
+                }
+
                 self.advance(1);
                 Expression::Variable(name.clone())
             }
-            Some(Token::BlockOpen) => self.parse_block(),
+            Some(Token::BlockOpen) => {
+                return if self.is_map_literal() {
+                    self.parse_map()
+                } else {
+                    self.parse_block()
+                };
+            }
             Some(Token::Quote) => {
                 self.advance(1);
 
@@ -428,6 +1430,7 @@ impl<'a> Parser<'a> {
 
                 Expression::List(items)
             }
+            Some(Token::Match) => return self.parse_match(),
             Some(Token::Prompt) => {
                 self.advance(1);
                 self.consume(&Token::ParenthesesOpen);
@@ -436,58 +1439,198 @@ impl<'a> Parser<'a> {
                 Expression::Prompt
             }
             _ => panic!("Unexpected token {:?} at position {}", token, self.position),
+        };
+
+        self.node(start, expression)
+    }
+
+    /*
+    An identifier directly followed by curly braces constructs a struct:
+        Point { x: 1, y: 2 }
+     */
+    fn is_struct_instance(&self) -> bool {
+        if self.tokens.get(self.position + 1) != Some(&Token::BlockOpen) {
+            return false;
+        }
+
+        match self.tokens.get(self.position + 2) {
+            Some(Token::BlockClosed) => true,
+            Some(Token::Identifier(_)) => self.tokens.get(self.position + 3) == Some(&Token::Colon),
+            _ => false,
         }
     }
 
+    fn parse_struct_instance(&mut self) -> Expression {
+        let start = self.position;
+        let identifier = self.parse_identifier();
+        self.consume(&Token::BlockOpen);
+
+        let mut fields = vec![];
+
+        while self.get_current() != Token::BlockClosed {
+            let field_start = self.position;
+            let field = self.parse_identifier();
+            self.consume(&Token::Colon);
+            let value = self.parse_statement();
+            fields.push(self.node(field_start, (field, value)));
+
+            match self.get_current() {
+                Token::BlockClosed => break,
+                Token::Comma => self.consume(&Token::Comma),
+                _ => panic!(
+                    "Unexpected token {:?} at position {}",
+                    self.get_current(),
+                    self.position
+                ),
+            }
+        }
+
+        self.consume(&Token::BlockClosed);
+
+        self.node(start, Expression::StructInstance { identifier, fields })
+    }
+
+    /*
+    Curly braces either open a block or a map literal. We treat them as a map when they are
+    empty or when the first entry starts with a literal key followed by a colon:
+        { "key": value }
+    Empty braces are always an empty map, an empty block would only evaluate to null anyway.
+    Keys have to be string, number, boolean or null literals, anything else like a variable or a
+    negative number is reported as an unsupported key instead of being taken for a block.
+     */
+    fn is_map_literal(&self) -> bool {
+        let colon_offset = match self.tokens.get(self.position + 1) {
+            Some(Token::BlockClosed) => return true,
+            Some(Token::Quote) => 4,
+            Some(
+                Token::Number(_)
+                | Token::Float(_)
+                | Token::BigInt(_)
+                | Token::Decimal(_)
+                | Token::True
+                | Token::False
+                | Token::Null,
+            ) => 2,
+            _ => return false,
+        };
+
+        self.tokens.get(self.position + colon_offset) == Some(&Token::Colon)
+    }
+
+    fn parse_map(&mut self) -> Expression {
+        let start = self.position;
+        self.consume(&Token::BlockOpen);
+
+        let mut entries = vec![];
+
+        while self.get_current() != Token::BlockClosed {
+            let entry_start = self.position;
+            let key = self.parse_expression();
+
+            if !matches!(
+                key,
+                Expression::String(_)
+                    | Expression::Number(_)
+                    | Expression::Float(_)
+                    | Expression::BigInt(_)
+                    | Expression::Decimal(_)
+                    | Expression::Boolean(_)
+                    | Expression::Null
+            ) {
+                unsupported_map_key(entry_start);
+            }
+
+            self.consume(&Token::Colon);
+            let value = self.parse_statement();
+            entries.push(self.node(entry_start, (key, value)));
+
+            match self.get_current() {
+                Token::BlockClosed => break,
+                Token::Comma => self.consume(&Token::Comma),
+                _ => panic!(
+                    "Unexpected token {:?} at position {}",
+                    self.get_current(),
+                    self.position
+                ),
+            }
+        }
+
+        self.consume(&Token::BlockClosed);
+
+        self.node(start, Expression::Map(entries))
+    }
+
     fn parse_block(&mut self) -> Expression {
+        let start = self.position;
         self.consume(&Token::BlockOpen);
 
         let mut expressions: Vec<Expression> = vec![];
+        let mut positions = vec![];
 
         while self.get_current() != Token::BlockClosed {
+            positions.extend(self.statement_position());
+            let span = self.start_statement();
             let expression = self.parse_statement();
             expressions.push(expression);
 
             match self.get_current() {
                 Token::Semicolon => self.advance(1),
-                Token::BlockClosed => break,
+                Token::BlockClosed => {}
+                // Only a map could have a colon here, with a key we don't support
+                Token::Colon => unsupported_map_key(self.statements[span].start),
                 _ => panic!("Expected ';' or '}}' in block"),
             }
+
+            self.statements[span].end = self.position;
         }
 
         self.consume(&Token::BlockClosed);
 
-        Expression::Block { expressions }
+        self.node(
+            start,
+            Expression::Block {
+                expressions,
+                positions,
+            },
+        )
     }
 }
 
+fn unsupported_map_key(position: usize) -> ! {
+    panic!(
+        "Unsupported map key at position {}, keys have to be string, number, boolean or null literals",
+        position
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::enums::Comparator;
+    use crate::tokenizer::{scan, tokenize};
 
     #[test]
     #[should_panic]
     fn invalid_unary_expression() {
-        parse(&vec![
-            Token::Operation(Operation::Multiply),
-            Token::Number(5),
-        ]);
+        parse(&[Token::Operation(Operation::Multiply), Token::Number(5)]);
     }
 
     #[test]
     fn assignment() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Remember,
                 Token::Identifier("test".to_string()),
                 Token::Equals,
                 Token::Number(15)
             ]),
             Program {
-                expressions: vec![Expression::Assign {
-                    name: "test".to_string(),
-                    value: Box::new(Expression::Number(15))
-                }]
+                expressions: vec![Expression::Declare {
+                    pattern: Pattern::Identifier("test".to_string()),
+                    value: Box::new(Expression::Number(15)),
+                    constant: false,
+                }],
+                positions: vec![],
             }
         )
     }
@@ -495,7 +1638,7 @@ mod tests {
     #[test]
     fn multiple_statements() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Remember,
                 Token::Identifier("test".to_string()),
                 Token::Equals,
@@ -507,24 +1650,548 @@ mod tests {
             ]),
             Program {
                 expressions: vec![
-                    Expression::Assign {
-                        name: "test".to_string(),
-                        value: Box::new(Expression::Number(15))
+                    Expression::Declare {
+                        pattern: Pattern::Identifier("test".to_string()),
+                        value: Box::new(Expression::Number(15)),
+                        constant: false,
                     },
                     Expression::Binary {
                         left: Box::new(Expression::Variable("test".to_string())),
                         operation: Operation::Add,
                         right: Box::new(Expression::Number(5)),
                     }
-                ]
+                ],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn destructuring_declaration() {
+        assert_eq!(
+            parse(&[
+                Token::Remember,
+                Token::BracketOpen,
+                Token::Identifier("a".to_string()),
+                Token::Comma,
+                Token::Ellipsis,
+                Token::Identifier("rest".to_string()),
+                Token::BracketClosed,
+                Token::Equals,
+                Token::Identifier("list".to_string()),
+            ]),
+            Program {
+                expressions: vec![Expression::Declare {
+                    pattern: Pattern::List {
+                        items: vec![Pattern::Identifier("a".to_string())],
+                        rest: Some("rest".to_string()),
+                    },
+                    value: Box::new(Expression::Variable("list".to_string())),
+                    constant: false,
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn constant_declaration_and_assignment() {
+        assert_eq!(
+            parse(&tokenize("fix x = 1; x = 2")),
+            Program {
+                expressions: vec![
+                    Expression::Declare {
+                        pattern: Pattern::Identifier("x".to_string()),
+                        value: Box::new(Expression::Number(1)),
+                        constant: true,
+                    },
+                    Expression::Assign {
+                        name: "x".to_string(),
+                        value: Box::new(Expression::Number(2)),
+                    }
+                ],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn destructuring_assignment() {
+        assert_eq!(
+            parse(&[
+                Token::BracketOpen,
+                Token::Identifier("a".to_string()),
+                Token::Comma,
+                Token::Identifier("b".to_string()),
+                Token::BracketClosed,
+                Token::Equals,
+                Token::BracketOpen,
+                Token::Identifier("b".to_string()),
+                Token::Comma,
+                Token::Identifier("a".to_string()),
+                Token::BracketClosed,
+            ]),
+            Program {
+                expressions: vec![Expression::Destructure {
+                    pattern: Pattern::List {
+                        items: vec![
+                            Pattern::Identifier("a".to_string()),
+                            Pattern::Identifier("b".to_string())
+                        ],
+                        rest: None,
+                    },
+                    value: Box::new(Expression::List(vec![
+                        Expression::Variable("b".to_string()),
+                        Expression::Variable("a".to_string())
+                    ]))
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn map() {
+        assert_eq!(
+            parse(&[
+                Token::BlockOpen,
+                Token::Quote,
+                Token::String("a".to_string()),
+                Token::Quote,
+                Token::Colon,
+                Token::Number(1),
+                Token::Comma,
+                Token::Number(2),
+                Token::Colon,
+                Token::True,
+                Token::BlockClosed,
+            ]),
+            Program {
+                expressions: vec![Expression::Map(vec![
+                    (Expression::String("a".to_string()), Expression::Number(1)),
+                    (Expression::Number(2), Expression::Boolean(true)),
+                ])],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn empty_braces_are_a_map() {
+        assert_eq!(
+            parse(&[Token::BlockOpen, Token::BlockClosed]),
+            Program {
+                expressions: vec![Expression::Map(vec![])],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported map key at position 1")]
+    fn identifier_map_key() {
+        parse(&tokenize("{ x: 1 }"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported map key at position 1")]
+    fn negative_map_key() {
+        parse(&tokenize("{ -1: \"a\" }"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported map key at position 7")]
+    fn unsupported_later_map_key() {
+        parse(&tokenize("{ \"a\": 1, b: 2 }"));
+    }
+
+    #[test]
+    fn index_assignment() {
+        assert_eq!(
+            parse(&[
+                Token::Identifier("m".to_string()),
+                Token::BracketOpen,
+                Token::Number(0),
+                Token::BracketClosed,
+                Token::Equals,
+                Token::Number(5),
+            ]),
+            Program {
+                expressions: vec![Expression::IndexAssign {
+                    target: Box::new(Expression::Variable("m".to_string())),
+                    index: Box::new(Expression::Number(0)),
+                    value: Box::new(Expression::Number(5)),
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn struct_declaration() {
+        assert_eq!(
+            parse(&[
+                Token::Struct,
+                Token::Identifier("Point".to_string()),
+                Token::BlockOpen,
+                Token::Identifier("x".to_string()),
+                Token::Comma,
+                Token::Identifier("y".to_string()),
+                Token::BlockClosed,
+            ]),
+            Program {
+                expressions: vec![Expression::Struct {
+                    identifier: "Point".to_string(),
+                    fields: vec!["x".to_string(), "y".to_string()],
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn struct_instance_and_field_access() {
+        assert_eq!(
+            parse(&[
+                Token::Identifier("Point".to_string()),
+                Token::BlockOpen,
+                Token::Identifier("x".to_string()),
+                Token::Colon,
+                Token::Number(1),
+                Token::BlockClosed,
+                Token::Dot,
+                Token::Identifier("x".to_string()),
+            ]),
+            Program {
+                expressions: vec![Expression::Field {
+                    target: Box::new(Expression::StructInstance {
+                        identifier: "Point".to_string(),
+                        fields: vec![("x".to_string(), Expression::Number(1))],
+                    }),
+                    field: "x".to_string(),
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn method_call() {
+        assert_eq!(
+            parse(&[
+                Token::Identifier("point".to_string()),
+                Token::Dot,
+                Token::Identifier("scale".to_string()),
+                Token::ParenthesesOpen,
+                Token::Number(2),
+                Token::ParenthesesClosed,
+            ]),
+            Program {
+                expressions: vec![Expression::MethodCall {
+                    target: Box::new(Expression::Variable("point".to_string())),
+                    method: "scale".to_string(),
+                    parameters: vec![Argument::Positional(Expression::Number(2))],
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    #[should_panic]
+    fn method_without_self() {
+        parse(&[
+            Token::Impl,
+            Token::Identifier("Point".to_string()),
+            Token::BlockOpen,
+            Token::Function,
+            Token::Identifier("length".to_string()),
+            Token::ParenthesesOpen,
+            Token::ParenthesesClosed,
+            Token::BlockOpen,
+            Token::BlockClosed,
+            Token::BlockClosed,
+        ]);
+    }
+
+    #[test]
+    fn enum_declaration() {
+        assert_eq!(
+            parse(&tokenize("enum Status { Pending, Done(result) }")),
+            Program {
+                expressions: vec![Expression::Enum {
+                    identifier: "Status".to_string(),
+                    variants: vec![
+                        ("Pending".to_string(), vec![]),
+                        ("Done".to_string(), vec!["result".to_string()]),
+                    ],
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn match_expression() {
+        assert_eq!(
+            parse(&tokenize(
+                "match (x) { Status::Done(r) if (r > 1) => r, 5 => 0, _ => null }"
+            )),
+            Program {
+                expressions: vec![Expression::Match {
+                    value: Box::new(Expression::Variable("x".to_string())),
+                    arms: vec![
+                        MatchArm {
+                            pattern: Pattern::Variant {
+                                identifier: "Status".to_string(),
+                                variant: "Done".to_string(),
+                                payload: vec![Pattern::Identifier("r".to_string())],
+                            },
+                            guard: Some(Expression::Comparison {
+                                left: Box::new(Expression::Variable("r".to_string())),
+                                comparator: Comparator::GreaterThan,
+                                right: Box::new(Expression::Number(1)),
+                            }),
+                            expression: Expression::Variable("r".to_string()),
+                        },
+                        MatchArm {
+                            pattern: Pattern::Literal(Box::new(Expression::Number(5))),
+                            guard: None,
+                            expression: Expression::Number(0),
+                        },
+                        MatchArm {
+                            pattern: Pattern::Wildcard,
+                            guard: None,
+                            expression: Expression::Null,
+                        },
+                    ],
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(
+            parse(&tokenize("0..=n + 1 step 2")),
+            Program {
+                expressions: vec![Expression::Range {
+                    start: Box::new(Expression::Number(0)),
+                    end: Box::new(Expression::Binary {
+                        left: Box::new(Expression::Variable("n".to_string())),
+                        operation: Operation::Add,
+                        right: Box::new(Expression::Number(1)),
+                    }),
+                    step: Some(Box::new(Expression::Number(2))),
+                    inclusive: true,
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(
+            parse(&tokenize("x[0] *= 2; y++")),
+            Program {
+                expressions: vec![
+                    Expression::CompoundAssign {
+                        target: Box::new(Expression::Index {
+                            target: Box::new(Expression::Variable("x".to_string())),
+                            index: Box::new(Expression::Number(0)),
+                        }),
+                        operation: Operation::Multiply,
+                        value: Box::new(Expression::Number(2)),
+                    },
+                    Expression::CompoundAssign {
+                        target: Box::new(Expression::Variable("y".to_string())),
+                        operation: Operation::Add,
+                        value: Box::new(Expression::Number(1)),
+                    }
+                ],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn function_parameters() {
+        assert_eq!(
+            parse(&tokenize("function f(a, b = 1, ...rest) {}")),
+            Program {
+                expressions: vec![Expression::Function {
+                    identifier: "f".to_string(),
+                    parameters: vec![
+                        Parameter {
+                            pattern: Pattern::Identifier("a".to_string()),
+                            default: None,
+                            variadic: false,
+                        },
+                        Parameter {
+                            pattern: Pattern::Identifier("b".to_string()),
+                            default: Some(Expression::Number(1)),
+                            variadic: false,
+                        },
+                        Parameter {
+                            pattern: Pattern::Identifier("rest".to_string()),
+                            default: None,
+                            variadic: true,
+                        },
+                    ],
+                    expression: Box::new(Expression::Block {
+                        expressions: vec![],
+                        positions: vec![],
+                    }),
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    #[should_panic]
+    fn rest_parameter_not_last() {
+        parse(&tokenize("function f(...rest, a) {}"));
+    }
+
+    #[test]
+    fn function_call_arguments() {
+        assert_eq!(
+            parse(&tokenize("remember x = f(1, ...list, name = 2)")),
+            Program {
+                expressions: vec![Expression::Declare {
+                    pattern: Pattern::Identifier("x".to_string()),
+                    constant: false,
+                    value: Box::new(Expression::FunctionCall {
+                        identifier: "f".to_string(),
+                        parameters: vec![
+                            Argument::Positional(Expression::Number(1)),
+                            Argument::Spread(Expression::Variable("list".to_string())),
+                            Argument::Named("name".to_string(), Expression::Number(2)),
+                        ],
+                    }),
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn statement_spans() {
+        let (_, spans) = parse_spans(&scan("remember x = 1; if (x) { x; 2 }"));
+
+        assert_eq!(
+            spans.statements,
+            vec![
+                Span { start: 0, end: 5 },
+                Span { start: 5, end: 14 },
+                Span { start: 10, end: 12 },
+                Span { start: 12, end: 13 },
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The payload of Shape::Circle can only be passed as positional values"
+    )]
+    fn named_variant_payload() {
+        parse(&tokenize("Shape::Circle(radius = 1)"));
+    }
+
+    #[test]
+    fn node_spans() {
+        let (_, spans) = parse_spans(&scan("x[0] += 1; a = f(1)"));
+
+        assert_eq!(
+            spans.nodes,
+            vec![
+                Span { start: 0, end: 1 },
+                Span { start: 2, end: 3 },
+                Span { start: 0, end: 4 },
+                Span { start: 5, end: 6 },
+                Span { start: 0, end: 6 },
+                Span { start: 11, end: 12 },
+                Span { start: 11, end: 12 },
+                Span { start: 9, end: 13 },
+                Span { start: 7, end: 13 },
+                Span { start: 0, end: 13 },
+            ]
+        );
+    }
+
+    #[test]
+    fn pipeline() {
+        assert_eq!(
+            parse(&tokenize("xs |> map(f) |> sum() == 1")),
+            Program {
+                expressions: vec![Expression::Comparison {
+                    left: Box::new(Expression::Pipeline {
+                        value: Box::new(Expression::Pipeline {
+                            value: Box::new(Expression::Variable("xs".to_string())),
+                            identifier: "map".to_string(),
+                            parameters: vec![Argument::Positional(Expression::Variable(
+                                "f".to_string()
+                            ))],
+                        }),
+                        identifier: "sum".to_string(),
+                        parameters: vec![],
+                    }),
+                    comparator: Comparator::Equality,
+                    right: Box::new(Expression::Number(1)),
+                }],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a function call after |>")]
+    fn pipeline_without_call() {
+        parse(&tokenize("xs |> sum"));
+    }
+
+    #[test]
+    fn import_and_export() {
+        assert_eq!(
+            parse(&tokenize(
+                "import \"lib/a.nali\"; import { b, c } from \"b.nali\"; export fix d = 1"
+            )),
+            Program {
+                expressions: vec![
+                    Expression::Import {
+                        path: "lib/a.nali".to_string(),
+                        names: None,
+                    },
+                    Expression::Import {
+                        path: "b.nali".to_string(),
+                        names: Some(vec!["b".to_string(), "c".to_string()]),
+                    },
+                    Expression::Export {
+                        expression: Box::new(Expression::Declare {
+                            pattern: Pattern::Identifier("d".to_string()),
+                            value: Box::new(Expression::Number(1)),
+                            constant: true,
+                        }),
+                    },
+                ],
+                positions: vec![],
             }
         )
     }
 
+    #[test]
+    #[should_panic(expected = "Only declarations can be exported")]
+    fn export_of_expression() {
+        parse(&tokenize("export 1 + 2"));
+    }
+
     #[test]
     fn yell() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Yell,
                 Token::ParenthesesOpen,
                 Token::Number(5),
@@ -533,7 +2200,8 @@ mod tests {
             Program {
                 expressions: vec![Expression::Yell {
                     expression: Box::new(Expression::Number(5))
-                },]
+                },],
+                positions: vec![],
             }
         )
     }
@@ -541,7 +2209,7 @@ mod tests {
     #[test]
     fn block() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::BlockOpen,
                 Token::Number(5),
                 Token::Operation(Operation::Add),
@@ -555,8 +2223,10 @@ mod tests {
                         left: Box::new(Expression::Number(5)),
                         operation: Operation::Add,
                         right: Box::new(Expression::Number(5)),
-                    }]
-                }]
+                    }],
+                    positions: vec![],
+                }],
+                positions: vec![],
             }
         )
     }
@@ -564,7 +2234,7 @@ mod tests {
     #[test]
     fn if_statement() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::If,
                 Token::ParenthesesOpen,
                 Token::True,
@@ -591,16 +2261,19 @@ mod tests {
                             left: Box::new(Expression::Number(5)),
                             operation: Operation::Add,
                             right: Box::new(Expression::Number(5)),
-                        }]
+                        }],
+                        positions: vec![],
                     },),
                     failure_expression: Some(Box::new(Expression::Block {
                         expressions: vec![Expression::Binary {
                             left: Box::new(Expression::Number(10)),
                             operation: Operation::Add,
                             right: Box::new(Expression::Number(10)),
-                        }]
+                        }],
+                        positions: vec![],
                     },))
-                }]
+                }],
+                positions: vec![],
             }
         )
     }
@@ -609,7 +2282,7 @@ mod tests {
     #[should_panic]
     fn if_statement_invalid() {
         // The if statement is missing a block
-        parse(&vec![
+        parse(&[
             Token::If,
             Token::ParenthesesOpen,
             Token::True,
@@ -620,13 +2293,49 @@ mod tests {
     #[test]
     fn string() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Quote,
                 Token::String("test".to_string()),
                 Token::Quote,
             ]),
             Program {
-                expressions: vec![Expression::String("test".to_string())]
+                expressions: vec![Expression::String("test".to_string())],
+                positions: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn logical_operators() {
+        // !a || b && c > 1 groups as (!a) || (b && (c > 1))
+        assert_eq!(
+            parse(&[
+                Token::Not,
+                Token::Identifier("a".to_string()),
+                Token::Or,
+                Token::Identifier("b".to_string()),
+                Token::And,
+                Token::Identifier("c".to_string()),
+                Token::Comparator(Comparator::GreaterThan),
+                Token::Number(1),
+            ]),
+            Program {
+                expressions: vec![Expression::Logical {
+                    left: Box::new(Expression::Not {
+                        expression: Box::new(Expression::Variable("a".to_string())),
+                    }),
+                    operator: Logical::Or,
+                    right: Box::new(Expression::Logical {
+                        left: Box::new(Expression::Variable("b".to_string())),
+                        operator: Logical::And,
+                        right: Box::new(Expression::Comparison {
+                            left: Box::new(Expression::Variable("c".to_string())),
+                            comparator: Comparator::GreaterThan,
+                            right: Box::new(Expression::Number(1)),
+                        }),
+                    }),
+                }],
+                positions: vec![],
             }
         )
     }
@@ -634,13 +2343,14 @@ mod tests {
     #[test]
     fn prompt() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Prompt,
                 Token::ParenthesesOpen,
                 Token::ParenthesesClosed
             ]),
             Program {
-                expressions: vec![Expression::Prompt]
+                expressions: vec![Expression::Prompt],
+                positions: vec![],
             }
         )
     }
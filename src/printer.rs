@@ -0,0 +1,951 @@
+use crate::enums::{
+    Argument, Comparator, Expression, Logical, MatchArm, Operation, Parameter, Pattern,
+};
+use crate::parser::{self, Program, Span};
+use crate::tokenizer::{self, Source};
+
+/*
+How tightly an expression binds, from whole statements up to postfix operations. A child that
+binds looser than its position requires gets wrapped in parentheses.
+ */
+const STATEMENT: u8 = 0;
+const ASSIGNMENT: u8 = 1;
+const OR: u8 = 2;
+const AND: u8 = 3;
+const COMPARISON: u8 = 4;
+const PIPELINE: u8 = 5;
+const RANGE: u8 = 6;
+const SUM: u8 = 7;
+const PRODUCT: u8 = 8;
+const UNARY: u8 = 9;
+const POSTFIX: u8 = 10;
+
+const INDENTATION: &str = "  ";
+
+struct Printer<'a> {
+    output: String,
+    depth: usize,
+    comments: Option<Comments<'a>>,
+    at_block_start: bool,
+    can_trail: bool,
+}
+
+/*
+The comments of the source being printed, handed out in order as the printer passes the
+statements they were written next to.
+ */
+struct Comments<'a> {
+    source: &'a Source,
+    statements: Vec<Span>,
+    next_statement: usize,
+    next_comment: usize,
+}
+
+/*
+Turns a program back into canonical nali source: one statement per line, each ending in a
+semicolon, blocks indented by two spaces and only the parentheses the grammar needs. For every
+tree the parser produces, parsing the printed source gives back the same tree.
+ */
+pub fn print(program: &Program) -> String {
+    Printer::new(None).print_program(program)
+}
+
+/*
+Prints a single expression, without the semicolon a statement ends with
+ */
+pub fn print_expression(expression: &Expression) -> String {
+    let mut printer = Printer::new(None);
+    printer.print_expression(expression, STATEMENT);
+    printer.output
+}
+
+/*
+Formats source code the way print would, but keeps its comments. A comment stays at the end of
+the line it was on or on its own line before the next statement, and single blank lines between
+statements are kept. Formatting already formatted code changes nothing.
+ */
+pub fn format(code: &str) -> String {
+    let source = tokenizer::scan(code);
+    let (program, spans) = parser::parse_spans(&source);
+
+    Printer::new(Some(Comments {
+        source: &source,
+        statements: spans.statements,
+        next_statement: 0,
+        next_comment: 0,
+    }))
+    .print_program(&program)
+}
+
+fn precedence(expression: &Expression) -> u8 {
+    match expression {
+        Expression::Declare { .. }
+        | Expression::ResolvedDeclare { .. }
+        | Expression::Yell { .. }
+        | Expression::If { .. }
+        | Expression::While { .. }
+        | Expression::For { .. }
+        | Expression::Function { .. }
+        | Expression::Struct { .. }
+        | Expression::Impl { .. }
+        | Expression::Enum { .. }
+        | Expression::Import { .. }
+        | Expression::Export { .. } => STATEMENT,
+        Expression::Assign { .. }
+        | Expression::ResolvedAssign { .. }
+        | Expression::IndexAssign { .. }
+        | Expression::FieldAssign { .. }
+        | Expression::CompoundAssign { .. }
+        | Expression::Destructure { .. } => ASSIGNMENT,
+        Expression::Logical {
+            operator: Logical::Or,
+            ..
+        } => OR,
+        Expression::Logical { .. } => AND,
+        Expression::Comparison { .. } => COMPARISON,
+        Expression::Pipeline { .. } | Expression::ResolvedPipeline { .. } => PIPELINE,
+        Expression::Range { .. } => RANGE,
+        Expression::Binary {
+            operation: Operation::Add | Operation::Subtract,
+            ..
+        } => SUM,
+        Expression::Binary { .. } => PRODUCT,
+        // Negative literals are written with a minus sign, which makes them unary operations
+        Expression::Unary { .. } | Expression::Not { .. } => UNARY,
+        Expression::Number(number) if *number < 0 => UNARY,
+        Expression::Float(float) if float.is_sign_negative() => UNARY,
+        Expression::Decimal(decimal) if decimal.is_negative() => UNARY,
+        _ => POSTFIX,
+    }
+}
+
+fn operation_symbol(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::Add => "+",
+        Operation::Subtract => "-",
+        Operation::Multiply => "*",
+        Operation::Divide => "/",
+        Operation::Modulo => "%",
+    }
+}
+
+fn comparator_symbol(comparator: &Comparator) -> &'static str {
+    match comparator {
+        Comparator::Equality => "==",
+        Comparator::LessThan => "<",
+        Comparator::GreaterThan => ">",
+        Comparator::In => "in",
+    }
+}
+
+/*
+Floats are written in the shortest form that parses back to the same float, always with a dot so
+they stay floats and never in scientific notation since the tokenizer does not understand it.
+There are no literals for infinity and NaN, so those are spelled out with float.
+ */
+fn format_float(float: f64) -> String {
+    let formatted = float.to_string();
+
+    if !float.is_finite() {
+        format!("float(\"{}\")", formatted)
+    } else if formatted.contains('.') {
+        formatted
+    } else {
+        format!("{}.0", formatted)
+    }
+}
+
+impl Comments<'_> {
+    fn line(&self, token: usize) -> usize {
+        self.source.locations[token].start.line
+    }
+
+    fn next_span(&mut self) -> Span {
+        let span = self.statements[self.next_statement];
+        self.next_statement += 1;
+        span
+    }
+
+    /*
+    Hands out the next comment if it comes before the given token, together with whether it
+    was written at the end of a line and whether an empty line came before it
+     */
+    fn take(&mut self, until: usize) -> Option<(String, bool, bool)> {
+        let comment = self.source.comments.get(self.next_comment)?;
+
+        if comment.token > until {
+            return None;
+        }
+
+        let previous_line = self.previous_line(comment.token);
+        self.next_comment += 1;
+
+        Some((
+            comment.text.clone(),
+            previous_line == Some(comment.line),
+            previous_line.is_some_and(|line| comment.line > line + 1),
+        ))
+    }
+
+    /*
+    The line of whatever was written last before the given token, be it a token or a comment
+     */
+    fn previous_line(&self, token: usize) -> Option<usize> {
+        let previous_comment = self.next_comment.checked_sub(1);
+
+        match previous_comment.map(|index| &self.source.comments[index]) {
+            Some(comment) if comment.token == token => Some(comment.line),
+            _ => token.checked_sub(1).map(|previous| self.line(previous)),
+        }
+    }
+
+    fn has_empty_line_before(&self, token: usize) -> bool {
+        self.previous_line(token)
+            .is_some_and(|line| self.line(token) > line + 1)
+    }
+}
+
+impl<'a> Printer<'a> {
+    fn new(comments: Option<Comments<'a>>) -> Self {
+        Printer {
+            output: String::new(),
+            depth: 0,
+            comments,
+            at_block_start: true,
+            can_trail: false,
+        }
+    }
+
+    fn print_program(mut self, program: &Program) -> String {
+        for expression in &program.expressions {
+            self.print_line(";", |printer| {
+                printer.print_expression(expression, STATEMENT)
+            });
+        }
+
+        self.print_comments(usize::MAX);
+
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output
+    }
+
+    fn push(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn new_line(&mut self) {
+        self.output.push('\n');
+        for _ in 0..self.depth {
+            self.output.push_str(INDENTATION);
+        }
+    }
+
+    fn line_break(&mut self, empty_line: bool) {
+        if self.output.is_empty() {
+            return;
+        }
+
+        if empty_line && !self.at_block_start {
+            self.output.push('\n');
+        }
+
+        self.new_line();
+    }
+
+    fn open_block(&mut self) {
+        self.push("{");
+        self.depth += 1;
+        self.at_block_start = true;
+        self.can_trail = true;
+    }
+
+    fn close_block(&mut self) {
+        self.at_block_start = false;
+        self.depth -= 1;
+        self.new_line();
+        self.push("}");
+    }
+
+    /*
+    Prints something that goes on its own line, like a statement, together with the comments
+    written before it and at the end of it
+     */
+    fn print_line(&mut self, terminator: &str, print_item: impl FnOnce(&mut Self)) {
+        let span = self.comments.as_mut().map(Comments::next_span);
+
+        if let Some(span) = span {
+            self.print_comments(span.start);
+        }
+
+        let empty_line = span.is_some_and(|span| {
+            self.comments
+                .as_ref()
+                .is_some_and(|comments| comments.has_empty_line_before(span.start))
+        });
+
+        self.line_break(empty_line);
+        print_item(self);
+        self.push(terminator);
+
+        self.at_block_start = false;
+        self.can_trail = true;
+
+        if let Some(span) = span {
+            self.print_comments(span.end);
+        }
+    }
+
+    fn print_comments(&mut self, until: usize) {
+        while let Some((text, trailing, empty_line)) = self
+            .comments
+            .as_mut()
+            .and_then(|comments| comments.take(until))
+        {
+            if trailing && self.can_trail {
+                self.push(" ");
+            } else {
+                self.line_break(empty_line);
+            }
+
+            self.push(&text);
+            self.at_block_start = false;
+            self.can_trail = false;
+        }
+    }
+
+    fn print_expression(&mut self, expression: &Expression, minimum: u8) {
+        if precedence(expression) < minimum {
+            self.push("(");
+            self.print_expression(expression, STATEMENT);
+            self.push(")");
+            return;
+        }
+
+        match expression {
+            Expression::Number(number) => self.push(&number.to_string()),
+            Expression::Float(float) => self.push(&format_float(*float)),
+            Expression::BigInt(big) => self.push(&big.to_string()),
+            Expression::Decimal(decimal) => self.push(&format!("{}d", decimal)),
+            Expression::String(string) => self.push(&format!("\"{}\"", string)),
+            Expression::Variable(name) | Expression::ResolvedVariable { name, .. } => {
+                self.push(name)
+            }
+            Expression::Boolean(boolean) => self.push(&boolean.to_string()),
+            Expression::Null => self.push("null"),
+            Expression::Prompt => self.push("prompt()"),
+            Expression::List(items) => {
+                self.push("[");
+                self.print_separated(items, |printer, item| {
+                    printer.print_expression(item, STATEMENT)
+                });
+                self.push("]");
+            }
+            Expression::Map(entries) => {
+                if entries.is_empty() {
+                    self.push("{}");
+                    return;
+                }
+
+                self.push("{ ");
+                self.print_separated(entries, |printer, (key, value)| {
+                    printer.print_expression(key, SUM);
+                    printer.push(": ");
+                    printer.print_expression(value, STATEMENT);
+                });
+                self.push(" }");
+            }
+            Expression::Block { expressions, .. } => self.print_block(expressions),
+            Expression::Index { target, index } => {
+                self.print_expression(target, POSTFIX);
+                self.push("[");
+                self.print_expression(index, RANGE);
+                self.push("]");
+            }
+            Expression::IndexAssign {
+                target,
+                index,
+                value,
+            } => {
+                self.print_expression(target, POSTFIX);
+                self.push("[");
+                self.print_expression(index, RANGE);
+                self.push("] = ");
+                self.print_expression(value, ASSIGNMENT);
+            }
+            Expression::Field { target, field } => {
+                self.print_expression(target, POSTFIX);
+                self.push(".");
+                self.push(field);
+            }
+            Expression::FieldAssign {
+                target,
+                field,
+                value,
+            } => {
+                self.print_expression(target, POSTFIX);
+                self.push(&format!(".{} = ", field));
+                self.print_expression(value, ASSIGNMENT);
+            }
+            Expression::Declare {
+                pattern,
+                value,
+                constant,
+            }
+            | Expression::ResolvedDeclare {
+                pattern,
+                value,
+                constant,
+                ..
+            } => {
+                self.push(if *constant { "fix " } else { "remember " });
+                self.print_pattern(pattern);
+                self.push(" = ");
+                self.print_expression(value, OR);
+            }
+            Expression::Assign { name, value } | Expression::ResolvedAssign { name, value, .. } => {
+                self.push(&format!("{} = ", name));
+                self.print_expression(value, ASSIGNMENT);
+            }
+            Expression::CompoundAssign {
+                target,
+                operation,
+                value,
+            } => {
+                self.print_expression(target, POSTFIX);
+                self.push(&format!(" {}= ", operation_symbol(operation)));
+                self.print_expression(value, ASSIGNMENT);
+            }
+            Expression::Destructure { pattern, value } => {
+                self.print_pattern(pattern);
+                self.push(" = ");
+                self.print_expression(value, ASSIGNMENT);
+            }
+            Expression::Yell { expression } => {
+                self.push("yell(");
+                self.print_expression(expression, OR);
+                self.push(")");
+            }
+            Expression::Binary {
+                left,
+                operation,
+                right,
+            } => {
+                let level = precedence(expression);
+
+                // Operations are left associative, so only the right side needs to bind tighter
+                self.print_expression(left, level);
+                self.push(&format!(" {} ", operation_symbol(operation)));
+                self.print_expression(right, level + 1);
+            }
+            Expression::Unary {
+                operation,
+                expression,
+            } => {
+                self.push(operation_symbol(operation));
+
+                // Keep two minuses apart so they are not read back as a decrement
+                if let Expression::Unary { .. } = **expression {
+                    self.push(" ");
+                }
+
+                self.print_expression(expression, UNARY);
+            }
+            Expression::If {
+                condition,
+                success_expression,
+                failure_expression,
+            } => {
+                self.push("if (");
+                self.print_expression(condition, OR);
+                self.push(") ");
+                self.print_body(success_expression);
+
+                if let Some(failure_expression) = failure_expression {
+                    self.push(" else ");
+                    self.print_body(failure_expression);
+                }
+            }
+            Expression::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                self.print_expression(start, SUM);
+                self.push(if *inclusive { "..=" } else { ".." });
+                self.print_expression(end, SUM);
+
+                if let Some(step) = step {
+                    self.push(" step ");
+                    self.print_expression(step, SUM);
+                }
+            }
+            Expression::Comparison {
+                left,
+                comparator,
+                right,
+            } => {
+                self.print_expression(left, PIPELINE);
+                self.push(&format!(" {} ", comparator_symbol(comparator)));
+                self.print_expression(right, PIPELINE);
+            }
+            Expression::Logical {
+                left,
+                operator: Logical::Or,
+                right,
+            } => {
+                self.print_expression(left, OR);
+                self.push(" || ");
+                self.print_expression(right, AND);
+            }
+            Expression::Logical { left, right, .. } => {
+                self.print_expression(left, AND);
+                self.push(" && ");
+                self.print_expression(right, COMPARISON);
+            }
+            Expression::Not { expression } => {
+                self.push("!");
+                self.print_expression(expression, UNARY);
+            }
+            Expression::While {
+                condition,
+                expression,
+            } => {
+                self.push("while (");
+                self.print_expression(condition, OR);
+                self.push(") ");
+                self.print_body(expression);
+            }
+            Expression::For {
+                pattern,
+                list,
+                expression,
+            } => {
+                self.push("for (");
+                self.print_pattern(pattern);
+                self.push(" in ");
+                self.print_expression(list, PIPELINE);
+                self.push(") ");
+                self.print_expression(expression, SUM);
+            }
+            Expression::Function {
+                identifier,
+                parameters,
+                expression,
+            } => {
+                self.push(&format!("function {}(", identifier));
+                self.print_separated(parameters, Printer::print_parameter);
+                self.push(") ");
+                self.print_body(expression);
+            }
+            Expression::FunctionCall {
+                identifier,
+                parameters,
+            }
+            | Expression::ResolvedFunctionCall {
+                identifier,
+                parameters,
+                ..
+            } => {
+                self.push(identifier);
+                self.print_arguments(parameters);
+            }
+            Expression::Struct { identifier, fields } => {
+                self.push(&format!(
+                    "struct {} {{ {} }}",
+                    identifier,
+                    fields.join(", ")
+                ));
+            }
+            Expression::Impl {
+                identifier,
+                methods,
+            } => {
+                self.push(&format!("impl {} ", identifier));
+                self.open_block();
+
+                for method in methods {
+                    self.print_line(";", |printer| printer.print_expression(method, STATEMENT));
+                }
+
+                self.close_block();
+            }
+            Expression::StructInstance { identifier, fields } => {
+                if fields.is_empty() {
+                    self.push(&format!("{} {{}}", identifier));
+                    return;
+                }
+
+                self.push(&format!("{} {{ ", identifier));
+                self.print_separated(fields, |printer, (field, value)| {
+                    printer.push(&format!("{}: ", field));
+                    printer.print_expression(value, STATEMENT);
+                });
+                self.push(" }");
+            }
+            Expression::Pipeline {
+                value,
+                identifier,
+                parameters,
+            }
+            | Expression::ResolvedPipeline {
+                value,
+                identifier,
+                parameters,
+                ..
+            } => {
+                self.print_expression(value, PIPELINE);
+                self.push(&format!(" |> {}", identifier));
+                self.print_arguments(parameters);
+            }
+            Expression::MethodCall {
+                target,
+                method,
+                parameters,
+            } => {
+                self.print_expression(target, POSTFIX);
+                self.push(&format!(".{}", method));
+                self.print_arguments(parameters);
+            }
+            Expression::Enum {
+                identifier,
+                variants,
+            } => {
+                self.push(&format!("enum {} {{ ", identifier));
+                self.print_separated(variants, |printer, (variant, payload)| {
+                    printer.push(variant);
+
+                    if !payload.is_empty() {
+                        printer.push(&format!("({})", payload.join(", ")));
+                    }
+                });
+                self.push(" }");
+            }
+            Expression::Variant {
+                identifier,
+                variant,
+                parameters,
+            } => {
+                self.push(&format!("{}::{}", identifier, variant));
+
+                if !parameters.is_empty() {
+                    self.push("(");
+                    self.print_separated(parameters, |printer, parameter| {
+                        printer.print_expression(parameter, OR)
+                    });
+                    self.push(")");
+                }
+            }
+            Expression::Match { value, arms } => {
+                self.push("match (");
+                self.print_expression(value, OR);
+                self.push(") ");
+                self.open_block();
+
+                for arm in arms {
+                    self.print_line(",", |printer| printer.print_arm(arm));
+                }
+
+                self.close_block();
+            }
+            Expression::Import { path, names } => {
+                self.push("import ");
+
+                if let Some(names) = names {
+                    self.push(&format!("{{ {} }} from ", names.join(", ")));
+                }
+
+                self.push(&format!("\"{}\"", path));
+            }
+            Expression::Export { expression } => {
+                self.push("export ");
+                self.print_expression(expression, STATEMENT);
+            }
+        }
+    }
+
+    fn print_separated<T>(&mut self, items: &[T], mut print_item: impl FnMut(&mut Self, &T)) {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.push(", ");
+            }
+
+            print_item(self, item);
+        }
+    }
+
+    fn print_block(&mut self, expressions: &[Expression]) {
+        if expressions.is_empty() {
+            self.push("{}");
+            return;
+        }
+
+        self.open_block();
+
+        for expression in expressions {
+            self.print_line(";", |printer| {
+                printer.print_expression(expression, STATEMENT)
+            });
+        }
+
+        self.close_block();
+    }
+
+    /*
+    Conditions, loops and functions always take a block as their body
+     */
+    fn print_body(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Block { expressions, .. } => self.print_block(expressions),
+            _ => self.print_block(std::slice::from_ref(expression)),
+        }
+    }
+
+    fn print_arguments(&mut self, arguments: &[Argument]) {
+        self.push("(");
+        self.print_separated(arguments, |printer, argument| match argument {
+            Argument::Positional(expression) => printer.print_expression(expression, OR),
+            Argument::Named(name, expression) => {
+                printer.push(&format!("{} = ", name));
+                printer.print_expression(expression, OR);
+            }
+            Argument::Spread(expression) => {
+                printer.push("...");
+                printer.print_expression(expression, OR);
+            }
+        });
+        self.push(")");
+    }
+
+    fn print_parameter(&mut self, parameter: &Parameter) {
+        if parameter.variadic {
+            self.push("...");
+        }
+
+        self.print_pattern(&parameter.pattern);
+
+        if let Some(default) = &parameter.default {
+            self.push(" = ");
+            self.print_expression(default, OR);
+        }
+    }
+
+    fn print_arm(&mut self, arm: &MatchArm) {
+        self.print_pattern(&arm.pattern);
+
+        if let Some(guard) = &arm.guard {
+            self.push(" if (");
+            self.print_expression(guard, OR);
+            self.push(")");
+        }
+
+        self.push(" => ");
+        self.print_expression(&arm.expression, STATEMENT);
+    }
+
+    fn print_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(identifier) => self.push(identifier),
+            Pattern::Wildcard => self.push("_"),
+            Pattern::Literal(literal) => self.print_expression(literal, POSTFIX),
+            Pattern::List { items, rest } => {
+                self.push("[");
+                self.print_separated(items, Printer::print_pattern);
+
+                if let Some(rest) = rest {
+                    if !items.is_empty() {
+                        self.push(", ");
+                    }
+
+                    self.push(&format!("...{}", rest));
+                }
+
+                self.push("]");
+            }
+            Pattern::Variant {
+                identifier,
+                variant,
+                payload,
+            } => {
+                self.push(&format!("{}::{}", identifier, variant));
+
+                if !payload.is_empty() {
+                    self.push("(");
+                    self.print_separated(payload, Printer::print_pattern);
+                    self.push(")");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenize;
+    use std::fs;
+
+    fn assert_round_trip(code: &str) {
+        let ast = parse(&tokenize(code));
+        let printed = print(&ast);
+
+        assert_eq!(parse(&tokenize(&printed)), ast, "printed as:\n{}", printed);
+        assert_eq!(print(&parse(&tokenize(&printed))), printed);
+    }
+
+    #[test]
+    fn logical_parentheses() {
+        assert_eq!(
+            print(&parse(&tokenize(
+                "(a && b) || c; a && (b || c); !(a > 1); (!a) && b"
+            ))),
+            "a && b || c;\na && (b || c);\n!(a > 1);\n!a && b;\n"
+        );
+    }
+
+    #[test]
+    fn canonical_output() {
+        assert_eq!(
+            print(&parse(&tokenize(
+                "remember x=(1+2)*3;if(x>5){yell(x)}else{x=x-1}"
+            ))),
+            "remember x = (1 + 2) * 3;\nif (x > 5) {\n  yell(x);\n} else {\n  x = x - 1;\n};\n"
+        );
+    }
+
+    #[test]
+    fn only_needed_parentheses() {
+        assert_eq!(
+            print(&parse(&tokenize(
+                "(a - (b - c)) - ((d * e) % f); -(-x); (y = 1) + 2"
+            ))),
+            "a - (b - c) - d * e % f;\n- -x;\n(y = 1) + 2;\n"
+        );
+    }
+
+    #[test]
+    fn floats_round_trip() {
+        for float in [
+            0.1,
+            0.1 + 0.2,
+            1e100,
+            1e-7,
+            -2.5,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+        ] {
+            let printed = print(&Program {
+                expressions: vec![Expression::Float(float)],
+                positions: vec![],
+            });
+
+            assert_eq!(
+                parse(&tokenize(&printed)).expressions,
+                vec![if float < 0.0 {
+                    Expression::Unary {
+                        operation: Operation::Subtract,
+                        expression: Box::new(Expression::Float(-float)),
+                    }
+                } else {
+                    Expression::Float(float)
+                }],
+                "{} was printed as {}",
+                float,
+                printed
+            );
+        }
+
+        assert_eq!(format_float(f64::INFINITY), "float(\"inf\")");
+        assert_eq!(format_float(f64::NAN), "float(\"NaN\")");
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(
+            "import \"lib/a.nali\"; import { b, c } from \"b.nali\";
+            export fix [first, ...rest] = [1, 2.5, 1.0, \"text\", true, null, {}, { \"a\": 1, 2: [3] }];
+            [first, rest] = [rest, first];
+            remember point = Point { x: 1, y: Point {} };
+            point.x += 2; point.y.z = point.items[0..=2 step 1]; point.items[1]--;
+            struct Point { x, y };
+            impl Point { function sum(self) { self.x + self.y }; function zero(self) { 0 } };
+            enum Status { Pending, Done(result, extra) };
+            function greet(name, [a, b], greeting = \"hi\", ...others) {
+                remember nested = { yell(name); greeting };
+                for ([key, value] in pairs) { while (key < 3) { key = key + 1 } };
+                nested
+            };
+            yell(name in [names, 123456789012345678901234567890, 12.50d, -0.5d]);
+            greet(\"bob\", ...list, greeting = 1 + 2);
+            match (Status::Done(5, 6)) {
+                Status::Done(result, _) if (result > 3) => { yell(result) },
+                Status::Pending => 1,
+                [1, \"a\", ...tail] => 2,
+                _ => point.sum() == -1,
+            };
+            remember value = (if (true) { 1 } else { 2 }) * 2;
+            for (x in 0..3 |> list() |> map(f)) { (x |> f()) |> g(1) > (1 |> h()) };
+            x = y = (yell(5));
+            while (!done && (a || b > 1)) { done = !(a && b) || c };
+            prompt()",
+        );
+    }
+
+    #[test]
+    fn examples_round_trip() {
+        for example in [
+            "hello_world",
+            "for_loop",
+            "while_loop",
+            "if",
+            "function",
+            "struct",
+            "prompt",
+        ] {
+            let code = fs::read_to_string(format!("./src/examples/{}.nali", example)).unwrap();
+            assert_round_trip(&code);
+        }
+    }
+
+    #[test]
+    fn format_keeps_comments() {
+        let formatted = format(
+            "// header\n\n\nremember x=1; // one\nfunction f(a){\n// inside\na*2 // double\n};\n// end",
+        );
+
+        assert_eq!(
+            formatted,
+            "// header\n\nremember x = 1; // one\nfunction f(a) {\n  // inside\n  a * 2; // double\n};\n// end\n"
+        );
+        assert_eq!(format(&formatted), formatted);
+    }
+
+    #[test]
+    fn format_is_idempotent() {
+        let code = "impl P { // methods\n function a(self) { [1, // one\n 2] }\n\n\n // second\n function b(self) { 2 } };
+            match (x) { // arms\n 1 => 2, // one\n _ => { 3 } // other\n }; if (x) { // empty\n }";
+        let formatted = format(code);
+
+        assert_eq!(format(&formatted), formatted);
+        assert_eq!(formatted.matches("//").count(), 7);
+    }
+
+    #[test]
+    fn examples_are_formatted() {
+        for path in fs::read_dir("./src/examples").unwrap() {
+            let path = path.unwrap().path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "nali")
+            {
+                let code = fs::read_to_string(&path).unwrap();
+                assert_eq!(format(&code), code, "{:?} is not formatted", path);
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt;
+
+/*
+A range of integers that is never materialized. Inclusive ranges are normalized to exclusive
+ones when they are created so there is only one representation to deal with.
+
+The bounds and the step are kept as i128, so ranges reaching up to the limits of i64 like
+0..=9223372036854775807 can be represented and calculations with them can't overflow. The
+items themselves always fit into an i64.
+ */
+#[derive(Clone, Copy)]
+pub struct Range {
+    start: i128,
+    end: i128,
+    step: i128,
+}
+
+impl Range {
+    pub fn new(start: i64, end: i64, step: i64, inclusive: bool) -> Self {
+        if step == 0 {
+            panic!("The step of a range can't be zero");
+        }
+
+        let step = step as i128;
+        let end = if inclusive {
+            end as i128 + step.signum()
+        } else {
+            end as i128
+        };
+        let range = Range {
+            start: start as i128,
+            end,
+            step,
+        };
+
+        if usize::try_from(range.count()).is_err() {
+            panic!("Range {:?} has too many items", range);
+        }
+
+        range
+    }
+
+    fn count(&self) -> i128 {
+        let distance = if self.step > 0 {
+            self.end - self.start
+        } else {
+            self.start - self.end
+        };
+
+        if distance <= 0 {
+            return 0;
+        }
+
+        let step = self.step.abs();
+        (distance + step - 1) / step
+    }
+
+    pub fn len(&self) -> usize {
+        self.count() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn item(&self, index: usize) -> i64 {
+        (self.start + index as i128 * self.step) as i64
+    }
+
+    pub fn get(&self, index: usize) -> Option<i64> {
+        if index < self.len() {
+            Some(self.item(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, number: i64) -> bool {
+        let number = number as i128;
+        let in_bounds = if self.step > 0 {
+            number >= self.start && number < self.end
+        } else {
+            number <= self.start && number > self.end
+        };
+
+        in_bounds && (number - self.start) % self.step == 0
+    }
+
+    /*
+    Slicing a range with another range picks the items at the indices of the second one, which
+    again gives us a range.
+     */
+    pub fn slice(&self, indices: &Range) -> Range {
+        let out_of_bounds = |index: i128| index < 0 || index >= self.count();
+
+        if !indices.is_empty()
+            && (out_of_bounds(indices.start)
+                || out_of_bounds(indices.start + (indices.count() - 1) * indices.step))
+        {
+            panic!(
+                "Slice {:?} is out of bounds for length {}",
+                indices,
+                self.len()
+            );
+        }
+
+        let start = self.start + indices.start * self.step;
+        let step = self.step * indices.step;
+
+        Range {
+            start,
+            end: start + indices.count() * step,
+            step,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..self.len()).map(|index| self.item(index))
+    }
+}
+
+/*
+Two ranges are equal when they produce the same items, so 0..10 step 3 and 0..11 step 3 are equal
+even though they were written with different bounds. All empty ranges are equal, and the step of a
+range with a single item does not matter.
+ */
+impl PartialEq for Range {
+    fn eq(&self, other: &Self) -> bool {
+        let count = self.count();
+
+        count == other.count()
+            && (count == 0 || self.start == other.start)
+            && (count <= 1 || self.step == other.step)
+    }
+}
+
+impl fmt::Debug for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.step == 1 {
+            write!(f, "{}..{}", self.start, self.end)
+        } else {
+            write!(f, "{}..{} step {}", self.start, self.end, self.step)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length() {
+        assert_eq!(Range::new(0, 10, 1, false).len(), 10);
+        assert_eq!(Range::new(0, 10, 1, true).len(), 11);
+        assert_eq!(Range::new(0, 10, 3, false).len(), 4);
+        assert_eq!(Range::new(10, 0, -2, false).len(), 5);
+        assert_eq!(Range::new(5, 0, 1, false).len(), 0);
+    }
+
+    #[test]
+    fn iteration() {
+        assert_eq!(
+            Range::new(10, 0, -3, true).iter().collect::<Vec<i64>>(),
+            vec![10, 7, 4, 1]
+        );
+    }
+
+    #[test]
+    fn contains() {
+        let range = Range::new(0, 10, 2, false);
+
+        assert!(range.contains(4));
+        assert!(!range.contains(5));
+        assert!(!range.contains(10));
+        assert!(!range.contains(-2));
+    }
+
+    #[test]
+    fn slice() {
+        let range = Range::new(0, 100, 10, false);
+
+        assert_eq!(
+            range
+                .slice(&Range::new(1, 4, 1, false))
+                .iter()
+                .collect::<Vec<i64>>(),
+            vec![10, 20, 30]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_out_of_bounds() {
+        Range::new(0, 5, 1, false).slice(&Range::new(3, 7, 1, false));
+    }
+
+    #[test]
+    fn limits_of_i64() {
+        let up_to_max = Range::new(0, i64::MAX, 1, true);
+        assert_eq!(up_to_max.len(), i64::MAX as usize + 1);
+        assert!(up_to_max.contains(i64::MAX));
+        assert_eq!(up_to_max.get(up_to_max.len() - 1), Some(i64::MAX));
+
+        assert_eq!(
+            Range::new(i64::MIN, i64::MAX, 1, false).len(),
+            u64::MAX as usize
+        );
+        assert!(Range::new(0, i64::MIN, -1, true).contains(i64::MIN));
+        assert_eq!(
+            Range::new(i64::MAX - 2, i64::MAX, 1, true)
+                .iter()
+                .collect::<Vec<i64>>(),
+            vec![i64::MAX - 2, i64::MAX - 1, i64::MAX]
+        );
+
+        let wide = Range::new(i64::MIN, i64::MAX, i64::MAX, false);
+        assert_eq!(
+            wide.slice(&Range::new(0, 3, 2, false))
+                .iter()
+                .collect::<Vec<i64>>(),
+            vec![i64::MIN, i64::MAX - 1]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "has too many items")]
+    fn too_many_items() {
+        Range::new(i64::MIN, i64::MAX, 1, true);
+    }
+
+    #[test]
+    fn equality_compares_items() {
+        assert!(Range::new(0, 10, 3, false) == Range::new(0, 11, 3, false));
+        assert!(Range::new(0, 9, 3, true) == Range::new(0, 10, 3, false));
+        assert!(Range::new(5, 0, 1, false) == Range::new(3, 3, 2, false));
+        assert!(Range::new(4, 5, 1, false) == Range::new(4, 0, -7, true));
+        assert!(Range::new(0, 10, 3, false) != Range::new(0, 10, 2, false));
+        assert!(Range::new(0, 10, 1, false) != Range::new(1, 11, 1, false));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_step() {
+        Range::new(0, 5, 0, false);
+    }
+}
@@ -0,0 +1,978 @@
+use crate::builtins;
+use crate::enums::{Argument, Expression, Parameter, Pattern};
+use crate::parser::Program;
+use std::collections::HashSet;
+
+/*
+Walks the program before it runs and binds every variable that is read, assigned or declared and
+every function that is called to the scope it is declared in and its slot there, so the
+interpreter can use it without looking up its name. The scopes are exactly the ones the
+interpreter creates: the program, blocks, the parameters of a function, an iteration of a for
+loop and a match arm. Names take their slots in the order the interpreter declares them, which is
+imports first, then the hoisted functions and then every declaration in the order it is
+evaluated, including the ones nested in expressions.
+
+Statements run in order, so inside of a function a variable is only visible after its
+declaration. The scopes around a function are visible as a whole, since the function might only
+be called once they are complete. Variables and functions that are declared nowhere, or only
+after they are used, are reported before the program runs.
+
+A declaration which might not run, like one behind an operator that short-circuits, moves the
+names after it to other slots, so from its slot on a scope is only used by name. When for loops
+leak their variables the scopes depend on the loops that ran, so then the names are only checked
+and the interpreter keeps looking them up by name.
+ */
+struct Resolver {
+    scopes: Vec<Scope>,
+    errors: Vec<String>,
+    leaky_loops: bool,
+    // How many expressions that might not run the resolver is in
+    conditional: usize,
+}
+
+struct Scope {
+    // Every name the scope declares, at the position of its slot
+    slots: Vec<String>,
+    declared: HashSet<String>,
+    // The parameters of a function, which is where the statements of a function start
+    function: bool,
+    // The first slot whose declaration might not run, from where on the slots may move
+    reliable: usize,
+}
+
+enum Lookup {
+    Found { depth: usize, slot: usize },
+    Later,
+    Missing,
+}
+
+pub fn resolve(program: &mut Program, imported: &[String], leaky_loops: bool) {
+    let mut resolver = Resolver {
+        scopes: vec![],
+        errors: vec![],
+        leaky_loops,
+        conditional: 0,
+    };
+
+    resolver.resolve_scope(&mut program.expressions, imported);
+
+    if !resolver.errors.is_empty() {
+        panic!("{}", resolver.errors.join("\n"));
+    }
+}
+
+impl Resolver {
+    /*
+    A block, or the program with the names it imports
+     */
+    fn resolve_scope(&mut self, expressions: &mut [Expression], imported: &[String]) {
+        self.push_scope(false);
+
+        for name in imported {
+            self.declare(name);
+        }
+
+        for expression in expressions.iter() {
+            if let Expression::Function { identifier, .. } = declaration(expression) {
+                self.declare(identifier);
+            }
+        }
+
+        for expression in expressions.iter() {
+            self.add_slots(expression);
+        }
+
+        for expression in expressions {
+            self.resolve_expression(expression);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn resolve_function(&mut self, parameters: &mut [Parameter], expression: &mut Expression) {
+        self.push_scope(true);
+
+        // Defaults are evaluated while binding, so they only see the parameters before them
+        for parameter in parameters {
+            if let Some(default) = &mut parameter.default {
+                // A default is only evaluated when its argument is missing
+                self.conditional += 1;
+                self.resolve_expression(default);
+                self.conditional -= 1;
+            }
+
+            for name in pattern_names(&parameter.pattern) {
+                self.declare(&name);
+            }
+        }
+
+        self.add_slots(expression);
+        self.resolve_expression(expression);
+        self.scopes.pop();
+    }
+
+    fn push_scope(&mut self, function: bool) {
+        self.scopes.push(Scope {
+            slots: vec![],
+            declared: HashSet::new(),
+            function,
+            reliable: usize::MAX,
+        });
+    }
+
+    fn add_slot(&mut self, name: &str, conditional: bool) {
+        let scope = self.scopes.last_mut().unwrap();
+
+        if !scope.slots.iter().any(|slot| slot == name) {
+            if conditional {
+                scope.reliable = scope.reliable.min(scope.slots.len());
+            }
+            scope.slots.push(name.to_string());
+        }
+    }
+
+    /*
+    Gives the names an expression declares in the current scope their slots, before it is
+    resolved, as functions might refer to them before their declaration
+     */
+    fn add_slots(&mut self, expression: &Expression) {
+        for (name, conditional) in scope_names(expression) {
+            self.add_slot(&name, conditional);
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        self.add_slot(name, self.conditional > 0);
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .declared
+            .insert(name.to_string());
+    }
+
+    /*
+    Finds the closest scope in which the name is visible at this point of the program
+     */
+    fn lookup(&self, name: &str) -> Lookup {
+        let mut declared_later = false;
+        let mut outside_function = false;
+
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(slot) = scope.slots.iter().position(|slot| slot == name) {
+                if outside_function || scope.declared.contains(name) {
+                    return Lookup::Found { depth, slot };
+                }
+
+                declared_later = true;
+            }
+
+            outside_function |= scope.function;
+        }
+
+        if declared_later {
+            Lookup::Later
+        } else {
+            Lookup::Missing
+        }
+    }
+
+    /*
+    Whether the interpreter will find the name in the slot, or has to look it up by name
+     */
+    fn reliable(&self, depth: usize, slot: usize) -> bool {
+        !self.leaky_loops && slot < self.scopes[self.scopes.len() - 1 - depth].reliable
+    }
+
+    fn error(&mut self, error: String) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    fn resolve_variable(&mut self, name: &str) -> Option<Expression> {
+        match self.lookup(name) {
+            Lookup::Found { depth, slot } if self.reliable(depth, slot) => {
+                Some(Expression::ResolvedVariable {
+                    name: name.to_string(),
+                    depth,
+                    slot,
+                })
+            }
+            Lookup::Found { .. } => None,
+            Lookup::Later => {
+                self.error(format!(
+                    "Variable '{}' is used before its declaration",
+                    name
+                ));
+                None
+            }
+            // Builtins are looked up by name, unless a variable shadows them
+            Lookup::Missing if builtins::get_builtin(name).is_some() => None,
+            Lookup::Missing => {
+                self.error(format!("Undefined variable '{}'", name));
+                None
+            }
+        }
+    }
+
+    fn resolve_assignment(&mut self, name: &str) -> Option<(usize, usize)> {
+        match self.lookup(name) {
+            Lookup::Found { depth, slot } if self.reliable(depth, slot) => Some((depth, slot)),
+            Lookup::Found { .. } => None,
+            Lookup::Later => {
+                self.error(format!(
+                    "Variable '{}' is used before its declaration",
+                    name
+                ));
+                None
+            }
+            Lookup::Missing => {
+                self.error(format!("Cannot assign to undeclared variable '{}'", name));
+                None
+            }
+        }
+    }
+
+    /*
+    The function a call refers to. A variable or parameter might hold a function, so it can be
+    called too.
+     */
+    fn resolve_call(&mut self, identifier: &str) -> Option<(usize, usize)> {
+        match self.lookup(identifier) {
+            Lookup::Found { depth, slot } if self.reliable(depth, slot) => Some((depth, slot)),
+            Lookup::Found { .. } => None,
+            Lookup::Later => {
+                self.error(format!(
+                    "Function '{}' is called before its declaration",
+                    identifier
+                ));
+                None
+            }
+            Lookup::Missing if builtins::get_builtin(identifier).is_some() => None,
+            Lookup::Missing => {
+                self.error(format!("Call to undefined function '{}'", identifier));
+                None
+            }
+        }
+    }
+
+    /*
+    The slots of the names a declaration binds in the current scope, unless one of them might
+    have moved
+     */
+    fn declare_pattern(&mut self, pattern: &Pattern) -> Option<Vec<usize>> {
+        let names = pattern_names(pattern);
+        for name in &names {
+            self.declare(name);
+        }
+
+        let scope = self.scopes.last().unwrap();
+        let slots: Vec<usize> = names
+            .iter()
+            .map(|name| scope.slots.iter().position(|slot| slot == name).unwrap())
+            .collect();
+
+        slots
+            .iter()
+            .all(|slot| self.reliable(0, *slot))
+            .then_some(slots)
+    }
+
+    fn resolve_arguments(&mut self, arguments: &mut [Argument]) {
+        for argument in arguments {
+            match argument {
+                Argument::Positional(expression)
+                | Argument::Named(_, expression)
+                | Argument::Spread(expression) => self.resolve_expression(expression),
+            }
+        }
+    }
+
+    fn resolve_expression(&mut self, expression: &mut Expression) {
+        if let Expression::Variable(name) = expression {
+            if let Some(resolved) = self.resolve_variable(name) {
+                *expression = resolved;
+            }
+            return;
+        }
+
+        match expression {
+            Expression::Number(_)
+            | Expression::Float(_)
+            | Expression::BigInt(_)
+            | Expression::Decimal(_)
+            | Expression::String(_)
+            | Expression::Variable(_)
+            | Expression::ResolvedVariable { .. }
+            | Expression::Boolean(_)
+            | Expression::Prompt
+            | Expression::Null
+            | Expression::Import { .. } => {}
+            Expression::Struct { identifier, .. } | Expression::Enum { identifier, .. } => {
+                self.declare(identifier)
+            }
+            Expression::List(items)
+            | Expression::Variant {
+                parameters: items, ..
+            } => {
+                for item in items {
+                    self.resolve_expression(item);
+                }
+            }
+            Expression::Map(entries) => {
+                for (key, value) in entries {
+                    self.resolve_expression(key);
+                    self.resolve_expression(value);
+                }
+            }
+            Expression::Block { expressions, .. } => self.resolve_scope(expressions, &[]),
+            Expression::Index { target, index } => {
+                self.resolve_expression(target);
+                self.resolve_expression(index);
+            }
+            Expression::IndexAssign {
+                target,
+                index,
+                value,
+            } => {
+                self.resolve_expression(target);
+                self.resolve_expression(index);
+                self.resolve_expression(value);
+            }
+            Expression::Field { target, .. } => self.resolve_expression(target),
+            Expression::FieldAssign { target, value, .. }
+            | Expression::CompoundAssign { target, value, .. } => {
+                self.resolve_expression(target);
+                self.resolve_expression(value);
+            }
+            Expression::Declare {
+                pattern,
+                value,
+                constant,
+            } => {
+                self.resolve_expression(value);
+
+                if let Some(slots) = self.declare_pattern(pattern) {
+                    *expression = Expression::ResolvedDeclare {
+                        pattern: pattern.clone(),
+                        value: std::mem::replace(value, Box::new(Expression::Null)),
+                        constant: *constant,
+                        slots,
+                    };
+                }
+            }
+            Expression::ResolvedDeclare { value, .. } => self.resolve_expression(value),
+            Expression::Assign { name, value } => {
+                self.resolve_expression(value);
+
+                if let Some((depth, slot)) = self.resolve_assignment(name) {
+                    let value = std::mem::replace(value, Box::new(Expression::Null));
+                    *expression = Expression::ResolvedAssign {
+                        name: name.clone(),
+                        depth,
+                        slot,
+                        value,
+                    };
+                }
+            }
+            Expression::ResolvedAssign { value, .. } => self.resolve_expression(value),
+            Expression::Destructure { pattern, value } => {
+                self.resolve_expression(value);
+                for name in pattern_names(pattern) {
+                    self.resolve_assignment(&name);
+                }
+            }
+            Expression::Yell { expression }
+            | Expression::Unary { expression, .. }
+            | Expression::Not { expression }
+            | Expression::Export { expression } => self.resolve_expression(expression),
+            Expression::Binary { left, right, .. }
+            | Expression::Comparison { left, right, .. }
+            | Expression::Logical { left, right, .. } => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expression::If {
+                condition,
+                success_expression,
+                failure_expression,
+            } => {
+                self.resolve_expression(condition);
+                self.resolve_expression(success_expression);
+                if let Some(failure_expression) = failure_expression {
+                    self.resolve_expression(failure_expression);
+                }
+            }
+            Expression::Range {
+                start, end, step, ..
+            } => {
+                self.resolve_expression(start);
+                self.resolve_expression(end);
+                if let Some(step) = step {
+                    self.resolve_expression(step);
+                }
+            }
+            Expression::While {
+                condition,
+                expression,
+            } => {
+                self.resolve_expression(condition);
+                self.resolve_expression(expression);
+            }
+            Expression::For {
+                pattern,
+                list,
+                expression,
+            } => {
+                self.resolve_expression(list);
+
+                if self.leaky_loops {
+                    for name in pattern_names(pattern) {
+                        self.declare(&name);
+                    }
+                    self.resolve_expression(expression);
+                    return;
+                }
+
+                self.push_scope(false);
+                for name in pattern_names(pattern) {
+                    self.declare(&name);
+                }
+                self.add_slots(expression);
+                self.resolve_expression(expression);
+                self.scopes.pop();
+            }
+            Expression::Function {
+                identifier,
+                parameters,
+                expression,
+            } => {
+                self.resolve_function(parameters, expression);
+                self.declare(identifier);
+            }
+            Expression::FunctionCall {
+                identifier,
+                parameters,
+            } => {
+                let target = self.resolve_call(identifier);
+                self.resolve_arguments(parameters);
+
+                if let Some((depth, slot)) = target {
+                    *expression = Expression::ResolvedFunctionCall {
+                        identifier: identifier.clone(),
+                        depth,
+                        slot,
+                        parameters: std::mem::take(parameters),
+                    };
+                }
+            }
+            Expression::ResolvedFunctionCall { parameters, .. } => {
+                self.resolve_arguments(parameters)
+            }
+            Expression::MethodCall {
+                target, parameters, ..
+            } => {
+                self.resolve_expression(target);
+                self.resolve_arguments(parameters);
+            }
+            Expression::Impl { methods, .. } => {
+                for method in methods {
+                    if let Expression::Function {
+                        parameters,
+                        expression,
+                        ..
+                    } = method
+                    {
+                        self.resolve_function(parameters, expression);
+                    }
+                }
+            }
+            Expression::StructInstance { fields, .. } => {
+                for (_, value) in fields {
+                    self.resolve_expression(value);
+                }
+            }
+            Expression::Pipeline {
+                value,
+                identifier,
+                parameters,
+            } => {
+                self.resolve_expression(value);
+                let target = self.resolve_call(identifier);
+                self.resolve_arguments(parameters);
+
+                if let Some((depth, slot)) = target {
+                    *expression = Expression::ResolvedPipeline {
+                        value: std::mem::replace(value, Box::new(Expression::Null)),
+                        identifier: identifier.clone(),
+                        depth,
+                        slot,
+                        parameters: std::mem::take(parameters),
+                    };
+                }
+            }
+            Expression::ResolvedPipeline {
+                value, parameters, ..
+            } => {
+                self.resolve_expression(value);
+                self.resolve_arguments(parameters);
+            }
+            Expression::Match { value, arms } => {
+                self.resolve_expression(value);
+
+                for arm in arms {
+                    self.push_scope(false);
+
+                    for name in pattern_names(&arm.pattern) {
+                        self.declare(&name);
+                    }
+                    // The guard runs first, so it declares its names first
+                    if let Some(guard) = &arm.guard {
+                        self.add_slots(guard);
+                    }
+                    self.add_slots(&arm.expression);
+
+                    if let Some(guard) = &mut arm.guard {
+                        self.resolve_expression(guard);
+                    }
+                    self.resolve_expression(&mut arm.expression);
+
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+}
+
+fn declaration(expression: &Expression) -> &Expression {
+    match expression {
+        Expression::Export { expression } => expression,
+        expression => expression,
+    }
+}
+
+/*
+The names an expression declares in the scope it runs in, in the order the interpreter evaluates
+them. Blocks, functions, the body of a for loop and match arms have scopes of their own, so what
+they declare is left out. Every name comes with whether its declaration might not run.
+ */
+fn scope_names(expression: &Expression) -> Vec<(String, bool)> {
+    let mut names = vec![];
+    collect_scope_names(expression, false, &mut names);
+    names
+}
+
+fn collect_scope_names(
+    expression: &Expression,
+    conditional: bool,
+    names: &mut Vec<(String, bool)>,
+) {
+    let mut collect = |expression: &Expression| collect_scope_names(expression, conditional, names);
+
+    match expression {
+        Expression::Number(_)
+        | Expression::Float(_)
+        | Expression::BigInt(_)
+        | Expression::Decimal(_)
+        | Expression::String(_)
+        | Expression::Variable(_)
+        | Expression::ResolvedVariable { .. }
+        | Expression::Boolean(_)
+        | Expression::Prompt
+        | Expression::Null
+        | Expression::Import { .. }
+        | Expression::Block { .. }
+        | Expression::Impl { .. } => {}
+        Expression::Function { identifier, .. }
+        | Expression::Struct { identifier, .. }
+        | Expression::Enum { identifier, .. } => names.push((identifier.clone(), conditional)),
+        Expression::Declare { pattern, value, .. }
+        | Expression::ResolvedDeclare { pattern, value, .. } => {
+            collect(value);
+            names.extend(
+                pattern_names(pattern)
+                    .into_iter()
+                    .map(|name| (name, conditional)),
+            );
+        }
+        Expression::List(items)
+        | Expression::Variant {
+            parameters: items, ..
+        } => items.iter().for_each(collect),
+        Expression::Map(entries) => {
+            for (key, value) in entries {
+                collect(key);
+                collect(value);
+            }
+        }
+        Expression::Index { target, index } => {
+            collect(target);
+            collect(index);
+        }
+        Expression::IndexAssign {
+            target,
+            index,
+            value,
+        } => {
+            collect(target);
+            collect(index);
+            collect(value);
+        }
+        Expression::FieldAssign { target, value, .. }
+        | Expression::CompoundAssign { target, value, .. } => {
+            collect(target);
+            collect(value);
+        }
+        Expression::Field {
+            target: expression, ..
+        }
+        | Expression::Assign {
+            value: expression, ..
+        }
+        | Expression::ResolvedAssign {
+            value: expression, ..
+        }
+        | Expression::Destructure {
+            value: expression, ..
+        }
+        | Expression::Yell { expression }
+        | Expression::Unary { expression, .. }
+        | Expression::Not { expression }
+        | Expression::Export { expression }
+        | Expression::For {
+            list: expression, ..
+        }
+        | Expression::Match {
+            value: expression, ..
+        } => collect(expression),
+        Expression::Binary { left, right, .. } | Expression::Comparison { left, right, .. } => {
+            collect(left);
+            collect(right);
+        }
+        Expression::Logical { left, right, .. } => {
+            collect(left);
+            collect_scope_names(right, true, names);
+        }
+        Expression::If {
+            condition,
+            success_expression,
+            failure_expression,
+        } => {
+            collect(condition);
+            collect_scope_names(success_expression, true, names);
+            if let Some(failure_expression) = failure_expression {
+                collect_scope_names(failure_expression, true, names);
+            }
+        }
+        Expression::Range {
+            start, end, step, ..
+        } => {
+            collect(start);
+            collect(end);
+            if let Some(step) = step {
+                collect(step);
+            }
+        }
+        Expression::While {
+            condition,
+            expression,
+        } => {
+            collect(condition);
+            collect_scope_names(expression, true, names);
+        }
+        Expression::FunctionCall { parameters, .. }
+        | Expression::ResolvedFunctionCall { parameters, .. } => {
+            collect_argument_names(parameters, conditional, names)
+        }
+        Expression::MethodCall {
+            target, parameters, ..
+        } => {
+            collect(target);
+            collect_argument_names(parameters, conditional, names);
+        }
+        Expression::Pipeline {
+            value, parameters, ..
+        }
+        | Expression::ResolvedPipeline {
+            value, parameters, ..
+        } => {
+            collect(value);
+            collect_argument_names(parameters, conditional, names);
+        }
+        Expression::StructInstance { fields, .. } => {
+            for (_, value) in fields {
+                collect(value);
+            }
+        }
+    }
+}
+
+fn collect_argument_names(
+    arguments: &[Argument],
+    conditional: bool,
+    names: &mut Vec<(String, bool)>,
+) {
+    for argument in arguments {
+        match argument {
+            Argument::Positional(expression)
+            | Argument::Named(_, expression)
+            | Argument::Spread(expression) => collect_scope_names(expression, conditional, names),
+        }
+    }
+}
+
+/*
+The names a pattern binds, in the order the interpreter binds them
+ */
+pub fn pattern_names(pattern: &Pattern) -> Vec<String> {
+    match pattern {
+        Pattern::Identifier(identifier) => vec![identifier.clone()],
+        Pattern::List { items, rest } => items
+            .iter()
+            .flat_map(pattern_names)
+            .chain(rest.clone())
+            .collect(),
+        Pattern::Variant { payload, .. } => payload.iter().flat_map(pattern_names).collect(),
+        Pattern::Wildcard | Pattern::Literal(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser, tokenizer};
+
+    fn resolve_code(code: &str) -> Program {
+        let mut program = parser::parse(&tokenizer::tokenize(code));
+        resolve(&mut program, &[], false);
+        program
+    }
+
+    fn resolved(name: &str, depth: usize, slot: usize) -> Expression {
+        Expression::ResolvedVariable {
+            name: name.to_string(),
+            depth,
+            slot,
+        }
+    }
+
+    #[test]
+    fn slots_follow_declaration_order() {
+        let program =
+            resolve_code("remember a = 1; function f() { 0 }; remember [b, c] = [2, 3]; c");
+
+        // f is hoisted, so it takes the first slot
+        assert_eq!(program.expressions[3], resolved("c", 0, 3));
+    }
+
+    #[test]
+    fn nested_declarations_take_slots_in_evaluation_order() {
+        let program = resolve_code("remember b = (remember c = 7); remember y = 2; y");
+
+        assert_eq!(program.expressions[2], resolved("y", 0, 2));
+    }
+
+    #[test]
+    fn assignments_are_resolved() {
+        let program = resolve_code("remember x = 1; { x = 2 }");
+
+        let Expression::Block { expressions, .. } = &program.expressions[1] else {
+            panic!("Expected a block");
+        };
+
+        assert_eq!(
+            expressions[0],
+            Expression::ResolvedAssign {
+                name: "x".to_string(),
+                depth: 1,
+                slot: 0,
+                value: Box::new(Expression::Number(2)),
+            }
+        );
+    }
+
+    #[test]
+    fn depth_counts_the_scopes_in_between() {
+        let program = resolve_code("remember a = 1; function f(x) { { x + a } }");
+
+        let Expression::Function { expression, .. } = &program.expressions[1] else {
+            panic!("Expected a function");
+        };
+        let Expression::Block { expressions, .. } = expression.as_ref() else {
+            panic!("Expected the body of the function");
+        };
+        let Expression::Block { expressions, .. } = &expressions[0] else {
+            panic!("Expected the inner block");
+        };
+
+        assert_eq!(
+            expressions[0],
+            Expression::Binary {
+                left: Box::new(resolved("x", 2, 0)),
+                operation: crate::enums::Operation::Add,
+                right: Box::new(resolved("a", 3, 1)),
+            }
+        );
+    }
+
+    #[test]
+    fn outer_variable_until_shadowed() {
+        let program = resolve_code("remember x = 1; { yell(x); remember x = 2; x }");
+
+        let Expression::Block { expressions, .. } = &program.expressions[1] else {
+            panic!("Expected a block");
+        };
+
+        assert_eq!(
+            expressions[0],
+            Expression::Yell {
+                expression: Box::new(resolved("x", 1, 0))
+            }
+        );
+        assert_eq!(expressions[2], resolved("x", 0, 0));
+    }
+
+    #[test]
+    fn builtins_stay_names() {
+        let program = resolve_code("remember f = len; f");
+
+        let Expression::ResolvedDeclare { value, .. } = &program.expressions[0] else {
+            panic!("Expected a declaration");
+        };
+        assert_eq!(**value, Expression::Variable("len".to_string()));
+    }
+
+    #[test]
+    fn calls_are_resolved() {
+        let program = resolve_code("function f(n) { f(n) }; remember x = 1; x |> f()");
+
+        assert_eq!(
+            program.expressions[2],
+            Expression::ResolvedPipeline {
+                value: Box::new(resolved("x", 0, 1)),
+                identifier: "f".to_string(),
+                depth: 0,
+                slot: 0,
+                parameters: vec![],
+            }
+        );
+
+        let Expression::Function { expression, .. } = &program.expressions[0] else {
+            panic!("Expected a function");
+        };
+        let Expression::Block { expressions, .. } = expression.as_ref() else {
+            panic!("Expected the body of the function");
+        };
+        assert_eq!(
+            expressions[0],
+            Expression::ResolvedFunctionCall {
+                identifier: "f".to_string(),
+                depth: 2,
+                slot: 0,
+                parameters: vec![Argument::Positional(resolved("n", 1, 0))],
+            }
+        );
+    }
+
+    #[test]
+    fn declarations_are_resolved() {
+        let program = resolve_code("remember a = 1; fix [b, c] = [2, 3]");
+
+        let Expression::ResolvedDeclare { slots, .. } = &program.expressions[1] else {
+            panic!("Expected a resolved declaration");
+        };
+        assert_eq!(*slots, vec![1, 2]);
+    }
+
+    #[test]
+    fn slots_after_a_declaration_that_might_not_run_are_looked_up() {
+        let program =
+            resolve_code("remember a = 1; false && (remember b = 2); remember c = 3; [a, c]");
+
+        assert!(matches!(program.expressions[2], Expression::Declare { .. }));
+        assert_eq!(
+            program.expressions[3],
+            Expression::List(vec![
+                resolved("a", 0, 0),
+                Expression::Variable("c".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn guards_take_slots_before_the_arm() {
+        let program =
+            resolve_code("match (1) { n if ((remember m = n) > 0) => (remember k = m) + k }");
+
+        let Expression::Match { arms, .. } = &program.expressions[0] else {
+            panic!("Expected a match");
+        };
+        let Expression::Binary { left, right, .. } = &arms[0].expression else {
+            panic!("Expected the arm expression");
+        };
+        let Expression::ResolvedDeclare { value, slots, .. } = left.as_ref() else {
+            panic!("Expected a resolved declaration");
+        };
+
+        assert_eq!(**value, resolved("m", 0, 1));
+        assert_eq!(*slots, vec![2]);
+        assert_eq!(**right, resolved("k", 0, 2));
+    }
+
+    #[test]
+    fn defined_functions() {
+        resolve_code("function a() { b() }; function b() { 1 }; a()");
+        resolve_code("len([1])");
+        resolve_code("function apply(f, [g]) { f(g()) }; remember h = apply; h(len, [len])");
+        resolve_code("for (f in [len]) { f([]) }; match (len) { f => f([]) }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Call to undefined function 'missing'")]
+    fn undefined_function() {
+        resolve_code("function a() { missing() }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Call to undefined function 'a'\nCall to undefined function 'b'")]
+    fn reports_every_undefined_function() {
+        resolve_code("a(); [b(), a()]");
+    }
+
+    #[test]
+    #[should_panic(expected = "Call to undefined function 'inner'")]
+    fn functions_are_not_visible_outside_their_block() {
+        resolve_code("{ function inner() { 1 } }; inner()");
+    }
+
+    #[test]
+    #[should_panic(expected = "Function 'f' is called before its declaration")]
+    fn call_before_declaration() {
+        resolve_code("{ f(); remember f = len }");
+    }
+
+    #[test]
+    fn functions_see_later_declarations_around_them() {
+        resolve_code("function f() { total }; remember total = 0; f()");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Undefined variable 'missing'\nCannot assign to undeclared variable 'other'"
+    )]
+    fn reports_undefined_names() {
+        resolve_code("function f() { missing + 1 }; other = 2; missing");
+    }
+
+    #[test]
+    #[should_panic(expected = "Variable 'x' is used before its declaration")]
+    fn use_before_declaration() {
+        resolve_code("{ yell(x); remember x = 1 }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined variable 'b'")]
+    fn defaults_only_see_earlier_parameters() {
+        resolve_code("function f(a = b, b = 1) { a }");
+    }
+}
@@ -1,18 +1,73 @@
+use crate::bigint::BigInt;
 use crate::cursor::Cursor;
+use crate::decimal::Decimal;
 use crate::enums::{Comparator, Operation, Token};
 
+/*
+A place in the source code, with both line and column counted from 1
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/*
+Where a token starts and the position right after its last character
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub start: Position,
+    pub end: Position,
+}
+
+/*
+A line comment, which is not part of the tokens. It remembers the index of the token following
+it so tools like the formatter can put it back in the right place.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub token: usize,
+    pub line: usize,
+}
+
+/*
+The tokens of a source file together with everything the interpreter itself does not need
+ */
+#[derive(Debug)]
+pub struct Source {
+    pub tokens: Vec<Token>,
+    pub locations: Vec<Location>,
+    pub comments: Vec<Comment>,
+}
+
 struct Tokenizer<'a> {
     characters: &'a [char],
     tokens: Vec<Token>,
     position: usize,
+    locations: Vec<Location>,
+    comments: Vec<Comment>,
+    line: usize,
+    line_start: usize,
+    scanned: usize,
 }
 
 pub fn tokenize(code_to_execute: &str) -> Vec<Token> {
+    scan(code_to_execute).tokens
+}
+
+pub fn scan(code_to_execute: &str) -> Source {
     let characters: Vec<char> = code_to_execute.chars().collect();
     let tokenizer = Tokenizer {
         characters: &characters,
         tokens: vec![],
         position: 0,
+        locations: vec![],
+        comments: vec![],
+        line: 1,
+        line_start: 0,
+        scanned: 0,
     };
 
     tokenizer.tokenize()
@@ -33,29 +88,55 @@ impl Cursor<char> for Tokenizer<'_> {
 }
 
 impl<'a> Tokenizer<'a> {
-    fn tokenize(mut self) -> Vec<Token> {
+    fn tokenize(mut self) -> Source {
         while self.has_next() {
+            let start = self.locate(self.position);
+
             if self.process_white_space() {
                 continue;
             }
 
             if self.process_string() {
+                self.locate_tokens(start);
+                continue;
+            }
+
+            if self.process_comment() {
                 continue;
             }
 
             if self.process_number() {
+                self.locate_tokens(start);
                 continue;
             }
 
             if self.process_equality() {
+                self.locate_tokens(start);
+                continue;
+            }
+
+            if self.process_ellipsis() {
+                self.locate_tokens(start);
+                continue;
+            }
+
+            if self.process_range() {
+                self.locate_tokens(start);
+                continue;
+            }
+
+            if self.process_double_character_tokens() {
+                self.locate_tokens(start);
                 continue;
             }
 
             if self.process_identifier() {
+                self.locate_tokens(start);
                 continue;
             }
 
             if self.process_basic_tokens() {
+                self.locate_tokens(start);
                 continue;
             }
 
@@ -66,7 +147,40 @@ impl<'a> Tokenizer<'a> {
             )
         }
 
-        self.tokens
+        Source {
+            tokens: self.tokens,
+            locations: self.locations,
+            comments: self.comments,
+        }
+    }
+
+    /*
+    Every token which has just been added spans from the given start to the current position
+     */
+    fn locate_tokens(&mut self, start: Position) {
+        let end = self.locate(self.position);
+        self.locations
+            .resize(self.tokens.len(), Location { start, end });
+    }
+
+    /*
+    Finds the line and column of a character. Tokens are located from left to right, so we only
+    ever have to look at the characters since the last lookup.
+     */
+    fn locate(&mut self, offset: usize) -> Position {
+        while self.scanned < offset {
+            if self.characters[self.scanned] == '\n' {
+                self.line += 1;
+                self.line_start = self.scanned + 1;
+            }
+
+            self.scanned += 1;
+        }
+
+        Position {
+            line: self.line,
+            column: offset - self.line_start + 1,
+        }
     }
 
     fn process_white_space(&mut self) -> bool {
@@ -107,6 +221,32 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /*
+    A comment starts with two slashes and goes until the end of the line:
+        // this is a comment
+     */
+    fn process_comment(&mut self) -> bool {
+        if self.items_left() < 2 || self.get_current() != '/' || self.get_next() != '/' {
+            return false;
+        }
+
+        let line = self.locate(self.position).line;
+
+        let mut text = String::new();
+        while self.has_next() && self.get_current() != '\n' {
+            text.push(self.get_current());
+            self.advance(1);
+        }
+
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            token: self.tokens.len(),
+            line,
+        });
+
+        true
+    }
+
     fn process_number(&mut self) -> bool {
         let character = self.get_current();
 
@@ -114,9 +254,14 @@ impl<'a> Tokenizer<'a> {
             let mut is_float = false;
             let mut number_string = String::new();
 
-            // Check if the number is a float or not
+            // Check if the number is a float or not. A dot only belongs to the number if a digit
+            // follows it, otherwise it could be the start of a range like 0..10
             while self.has_next()
-                && (self.get_current().is_ascii_digit() || self.get_current() == '.')
+                && (self.get_current().is_ascii_digit()
+                    || (self.get_current() == '.'
+                        && !is_float
+                        && self.items_left() > 1
+                        && self.get_next().is_ascii_digit()))
             {
                 if self.get_current() == '.' {
                     is_float = true;
@@ -126,12 +271,26 @@ impl<'a> Tokenizer<'a> {
                 self.advance(1);
             }
 
-            if is_float {
+            // A d right after the digits makes the number a decimal, like 12.50d
+            let is_decimal = self.has_next()
+                && self.get_current() == 'd'
+                && !(self.items_left() > 1 && is_identifier_character(self.get_next(), false));
+
+            if is_decimal {
+                self.advance(1);
+                self.tokens
+                    .push(Token::Decimal(Decimal::parse(&number_string).unwrap()))
+            } else if is_float {
                 self.tokens
                     .push(Token::Float(number_string.parse().unwrap()))
             } else {
-                self.tokens
-                    .push(Token::Number(number_string.parse().unwrap()))
+                // Integers which don't fit into an i64 become big integers
+                match number_string.parse() {
+                    Ok(number) => self.tokens.push(Token::Number(number)),
+                    Err(_) => self
+                        .tokens
+                        .push(Token::BigInt(BigInt::parse(&number_string).unwrap())),
+                }
             }
 
             return true;
@@ -153,6 +312,7 @@ impl<'a> Tokenizer<'a> {
 
             let token = match identifier.as_str() {
                 "remember" => Token::Remember,
+                "fix" => Token::Fix,
                 "yell" => Token::Yell,
                 "true" => Token::True,
                 "false" => Token::False,
@@ -164,6 +324,12 @@ impl<'a> Tokenizer<'a> {
                 "in" => Token::In,
                 "function" => Token::Function,
                 "prompt" => Token::Prompt,
+                "struct" => Token::Struct,
+                "impl" => Token::Impl,
+                "enum" => Token::Enum,
+                "match" => Token::Match,
+                "import" => Token::Import,
+                "export" => Token::Export,
                 _ => Token::Identifier(identifier),
             };
             self.tokens.push(token);
@@ -191,6 +357,76 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    fn process_double_character_tokens(&mut self) -> bool {
+        if self.items_left() < 2 {
+            return false;
+        }
+
+        let token = match (self.get_current(), self.get_next()) {
+            ('=', '>') => Token::FatArrow,
+            ('|', '>') => Token::Pipe,
+            ('&', '&') => Token::And,
+            ('|', '|') => Token::Or,
+            (':', ':') => Token::DoubleColon,
+            ('+', '=') => Token::CompoundAssign(Operation::Add),
+            ('-', '=') => Token::CompoundAssign(Operation::Subtract),
+            ('*', '=') => Token::CompoundAssign(Operation::Multiply),
+            ('/', '=') => Token::CompoundAssign(Operation::Divide),
+            ('%', '=') => Token::CompoundAssign(Operation::Modulo),
+            // Only something that can be assigned to can be incremented, everywhere else two
+            // pluses or minuses in a row are unary operators like in --5
+            ('+', '+') if self.follows_place() => Token::Increment,
+            ('-', '-') if self.follows_place() => Token::Decrement,
+            _ => return false,
+        };
+
+        self.tokens.push(token);
+        self.advance(2);
+
+        true
+    }
+
+    fn follows_place(&self) -> bool {
+        matches!(
+            self.tokens.last(),
+            Some(Token::Identifier(_) | Token::BracketClosed)
+        )
+    }
+
+    fn process_ellipsis(&mut self) -> bool {
+        if self.items_left() < 3 {
+            return false;
+        }
+
+        let is_ellipsis = self.characters[self.position..self.position + 3]
+            .iter()
+            .all(|character| *character == '.');
+
+        if is_ellipsis {
+            self.tokens.push(Token::Ellipsis);
+            self.advance(3);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn process_range(&mut self) -> bool {
+        if self.items_left() < 2 || self.get_current() != '.' || self.get_next() != '.' {
+            return false;
+        }
+
+        if self.characters.get(self.position + 2) == Some(&'=') {
+            self.tokens.push(Token::DotDotEquals);
+            self.advance(3);
+        } else {
+            self.tokens.push(Token::DotDot);
+            self.advance(2);
+        }
+
+        true
+    }
+
     fn process_basic_tokens(&mut self) -> bool {
         let character = self.get_current();
 
@@ -199,9 +435,11 @@ impl<'a> Tokenizer<'a> {
             '-' => self.tokens.push(Token::Operation(Operation::Subtract)),
             '*' => self.tokens.push(Token::Operation(Operation::Multiply)),
             '/' => self.tokens.push(Token::Operation(Operation::Divide)),
+            '%' => self.tokens.push(Token::Operation(Operation::Modulo)),
             '(' => self.tokens.push(Token::ParenthesesOpen),
             ')' => self.tokens.push(Token::ParenthesesClosed),
             '=' => self.tokens.push(Token::Equals),
+            '!' => self.tokens.push(Token::Not),
             ';' => self.tokens.push(Token::Semicolon),
             '{' => self.tokens.push(Token::BlockOpen),
             '}' => self.tokens.push(Token::BlockClosed),
@@ -211,6 +449,8 @@ impl<'a> Tokenizer<'a> {
             '[' => self.tokens.push(Token::BracketOpen),
             ']' => self.tokens.push(Token::BracketClosed),
             ',' => self.tokens.push(Token::Comma),
+            ':' => self.tokens.push(Token::Colon),
+            '.' => self.tokens.push(Token::Dot),
             _ => return false,
         }
 
@@ -309,6 +549,182 @@ mod tests {
         assert_eq!(tokenize("if"), vec![Token::If])
     }
 
+    #[test]
+    fn ellipsis() {
+        assert_eq!(
+            tokenize("[a, ...rest]"),
+            vec![
+                Token::BracketOpen,
+                Token::Identifier("a".to_string()),
+                Token::Comma,
+                Token::Ellipsis,
+                Token::Identifier("rest".to_string()),
+                Token::BracketClosed,
+            ]
+        )
+    }
+
+    #[test]
+    fn field_access() {
+        assert_eq!(
+            tokenize("point.x"),
+            vec![
+                Token::Identifier("point".to_string()),
+                Token::Dot,
+                Token::Identifier("x".to_string()),
+            ]
+        )
+    }
+
+    #[test]
+    fn enum_variant_and_match_arm() {
+        assert_eq!(
+            tokenize("Status::Done => x"),
+            vec![
+                Token::Identifier("Status".to_string()),
+                Token::DoubleColon,
+                Token::Identifier("Done".to_string()),
+                Token::FatArrow,
+                Token::Identifier("x".to_string()),
+            ]
+        )
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            tokenize("0..10"),
+            vec![Token::Number(0), Token::DotDot, Token::Number(10)]
+        );
+        assert_eq!(
+            tokenize("0..=1.5"),
+            vec![Token::Number(0), Token::DotDotEquals, Token::Float(1.5)]
+        );
+    }
+
+    #[test]
+    fn big_integers() {
+        assert_eq!(
+            tokenize("9223372036854775807 9223372036854775808"),
+            vec![
+                Token::Number(i64::MAX),
+                Token::BigInt(BigInt::parse("9223372036854775808").unwrap()),
+            ]
+        )
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(
+            tokenize("12.50d 3d 4.do()"),
+            vec![
+                Token::Decimal(Decimal::parse("12.50").unwrap()),
+                Token::Decimal(Decimal::parse("3").unwrap()),
+                Token::Number(4),
+                Token::Dot,
+                Token::Identifier("do".to_string()),
+                Token::ParenthesesOpen,
+                Token::ParenthesesClosed,
+            ]
+        )
+    }
+
+    #[test]
+    fn pipe() {
+        assert_eq!(
+            tokenize("xs |> sum()"),
+            vec![
+                Token::Identifier("xs".to_string()),
+                Token::Pipe,
+                Token::Identifier("sum".to_string()),
+                Token::ParenthesesOpen,
+                Token::ParenthesesClosed,
+            ]
+        )
+    }
+
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(
+            tokenize("x += 1; x %= 2"),
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::CompoundAssign(Operation::Add),
+                Token::Number(1),
+                Token::Semicolon,
+                Token::Identifier("x".to_string()),
+                Token::CompoundAssign(Operation::Modulo),
+                Token::Number(2),
+            ]
+        )
+    }
+
+    #[test]
+    fn increment_only_after_place() {
+        assert_eq!(
+            tokenize("x++; --5"),
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Increment,
+                Token::Semicolon,
+                Token::Operation(Operation::Subtract),
+                Token::Operation(Operation::Subtract),
+                Token::Number(5),
+            ]
+        )
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            tokenize("// nothing to see\nx / 2 // half"),
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Operation(Operation::Divide),
+                Token::Number(2),
+            ]
+        );
+
+        assert_eq!(
+            scan("a;\n// note\nb").comments,
+            vec![Comment {
+                text: "// note".to_string(),
+                token: 2,
+                line: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn locations() {
+        let locations: Vec<_> = scan("remember x = \"a\";\n  x")
+            .locations
+            .iter()
+            .map(|location| {
+                (
+                    location.start.line,
+                    location.start.column,
+                    location.end.line,
+                    location.end.column,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            locations,
+            vec![
+                (1, 1, 1, 9),
+                (1, 10, 1, 11),
+                (1, 12, 1, 13),
+                (1, 14, 1, 17),
+                (1, 14, 1, 17),
+                (1, 14, 1, 17),
+                (1, 17, 1, 18),
+                (2, 3, 2, 4),
+            ]
+        );
+    }
+
     #[test]
     fn equality_support() {
         assert_eq!(